@@ -0,0 +1,54 @@
+#!/usr/bin/env node
+
+// Benchmark loading a Matrix Market (.mtx) file, such as the SuiteSparse and
+// SNAP datasets, and sorting the graph.
+// Usage: ./example_js/benchmark_mtx.js path/to/graph.mtx [--undirected]
+
+let petgraph = require('../pkg/petgraph_wasm')
+let fs = require('fs')
+let process = require('process')
+
+const cliArgs = process.argv
+const path = cliArgs[2]
+const undirected = cliArgs[3] === '--undirected'
+if (!path) {
+    console.error('Usage: benchmark_mtx.js path/to/graph.mtx [--undirected]')
+    process.exit(1)
+}
+
+async function main() {
+    const loadText = `Stream ${path} into ${undirected ? 'UnGraph' : 'DiGraph'}`
+    console.time(loadText)
+    const reader = new petgraph.MatrixMarketReader()
+    // Chunks are parsed as they arrive, so the file is never held as one string
+    for await (const chunk of fs.createReadStream(path)) {
+        reader.push(chunk)
+    }
+    const g = undirected ? reader.finishUndirected() : reader.finish()
+    console.timeEnd(loadText)
+    console.log(`Read node count ... ${g.nodeCount()}`)
+    console.log(`Read edge count ... ${g.edgeCount()}`)
+
+    if (!undirected) {
+        const sortText = `Sort ${g.nodeCount()} nodes with ${g.edgeCount()} edges`
+        let sortError
+        console.time(sortText)
+        try {
+            petgraph.toposort(g)
+        } catch (error) {
+            sortError = error
+        }
+        console.timeEnd(sortText)
+        console.log(sortError ? 'Graph has a cycle' : 'Graph is acyclic')
+    }
+
+    const memory = process.memoryUsage()
+    const rss_used = Math.round(memory.rss / 1024 / 1024)
+    const heap_used = Math.round(memory.heapUsed / 1024 / 1024)
+    console.log(`The script used ~${rss_used} MB with heap use of ~${heap_used} MB`)
+}
+
+main().catch((error) => {
+    console.error(error)
+    process.exit(1)
+})
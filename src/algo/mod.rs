@@ -38,7 +38,7 @@ mod tests {
     #[wasm_bindgen_test]
     fn can_detect_cycles() {
         let (mut g, _nodes, _edges) = new_test_graph();
-        g.add_edge(2, 1, JsValue::NULL);
+        g.add_edge(2, 1, JsValue::NULL).unwrap();
         let sort_err = toposort(&g).unwrap_err();
         let expect_err = GraphError::new("Cycle detected", GraphItemType::Node, 1);
         assert_eq!(sort_err.into_serde::<GraphError>().unwrap(), expect_err);
@@ -0,0 +1,494 @@
+//! Adjacency matrices of numeric edge weights, as a dense row-major
+//! `Float64Array` or as sparse COO and CSR typed arrays.
+//!
+//! Entries are `(row, column, value)` triples where the row is the source
+//! node and the column the target node. Parallel edges are summed into one
+//! entry.
+
+use crate::formats::MAX_NODE_COUNT;
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::{get_bool_option, get_option, get_string_option};
+use crate::{GraphError, GraphItemType};
+use js_sys::{Array, Float64Array, Function, Object, Reflect, Uint32Array};
+use petgraph::graph::{self, NodeIndex};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+pub type MatrixEntry = (usize, usize, f64);
+
+/// Largest number of values of a dense matrix, 4096 × 4096 or 128 MB.
+pub const MAX_DENSE_VALUES: usize = 1 << 24;
+
+fn invalid_matrix(message: &str) -> GraphError {
+    GraphError::from_message(&format!("Invalid adjacency matrix: {}", message))
+}
+
+/// Sum the weights of edges with the same endpoints, giving entries sorted by
+/// row and column.
+pub fn matrix_entries(edges: impl Iterator<Item = MatrixEntry>) -> Vec<MatrixEntry> {
+    let mut sums = BTreeMap::new();
+    for (row, column, value) in edges {
+        *sums.entry((row, column)).or_insert(0.0) += value;
+    }
+    sums.into_iter()
+        .map(|((row, column), value)| (row, column, value))
+        .collect()
+}
+
+/// Row-major dense `n` × `n` matrix of entries, with at most
+/// `MAX_DENSE_VALUES` values.
+pub fn dense_matrix(n: usize, entries: &[MatrixEntry]) -> Result<Vec<f64>, GraphError> {
+    let len = n
+        .checked_mul(n)
+        .filter(|&len| len <= MAX_DENSE_VALUES)
+        .ok_or_else(|| {
+            GraphError::from_message(&format!(
+                "Too many nodes ({}) for a dense adjacency matrix, use a sparse one",
+                n
+            ))
+        })?;
+    let mut data = vec![0.0; len];
+    for &(row, column, value) in entries {
+        data[row * n + column] += value;
+    }
+    Ok(data)
+}
+
+/// Compressed sparse row arrays `(indptr, indices, values)` of entries
+/// sorted by row.
+pub fn csr_matrix(n: usize, entries: &[MatrixEntry]) -> (Vec<u32>, Vec<u32>, Vec<f64>) {
+    let mut indptr = vec![0; n + 1];
+    for &(row, _, _) in entries {
+        indptr[row + 1] += 1;
+    }
+    for row in 0..n {
+        indptr[row + 1] += indptr[row];
+    }
+    let indices = entries
+        .iter()
+        .map(|&(_, column, _)| column as u32)
+        .collect();
+    let values = entries.iter().map(|&(_, _, value)| value).collect();
+    (indptr, indices, values)
+}
+
+/// Entries of the non-zero values of a row-major dense `n` × `n` matrix.
+pub fn dense_entries(n: usize, data: &[f64]) -> Result<Vec<MatrixEntry>, GraphError> {
+    let expected = n as u64 * n as u64;
+    if data.len() as u64 != expected {
+        return Err(invalid_matrix(&format!(
+            "expected {} values for shape [{}, {}], found {}",
+            expected,
+            n,
+            n,
+            data.len()
+        )));
+    }
+    Ok(data
+        .iter()
+        .enumerate()
+        .filter(|(_, &value)| value != 0.0)
+        .map(|(index, &value)| (index / n, index % n, value))
+        .collect())
+}
+
+fn check_index(n: usize, index: u32, name: &str) -> Result<usize, GraphError> {
+    if (index as usize) < n {
+        Ok(index as usize)
+    } else {
+        Err(invalid_matrix(&format!(
+            "{} index {} is out of bounds for {} nodes",
+            name, index, n
+        )))
+    }
+}
+
+fn check_values_length(expected: usize, values: &[f64]) -> Result<(), GraphError> {
+    if values.len() == expected {
+        Ok(())
+    } else {
+        Err(invalid_matrix(&format!(
+            "expected {} values, found {}",
+            expected,
+            values.len()
+        )))
+    }
+}
+
+/// Entries of a sparse matrix in coordinate format.
+pub fn coo_entries(
+    n: usize,
+    rows: &[u32],
+    columns: &[u32],
+    values: &[f64],
+) -> Result<Vec<MatrixEntry>, GraphError> {
+    if rows.len() != columns.len() {
+        return Err(invalid_matrix(&format!(
+            "found {} rows but {} columns",
+            rows.len(),
+            columns.len()
+        )));
+    }
+    check_values_length(rows.len(), values)?;
+    rows.iter()
+        .zip(columns)
+        .zip(values)
+        .map(|((&row, &column), &value)| {
+            Ok((
+                check_index(n, row, "row")?,
+                check_index(n, column, "column")?,
+                value,
+            ))
+        })
+        .collect()
+}
+
+/// Entries of a sparse matrix in compressed sparse row format.
+pub fn csr_entries(
+    n: usize,
+    indptr: &[u32],
+    indices: &[u32],
+    values: &[f64],
+) -> Result<Vec<MatrixEntry>, GraphError> {
+    if indptr.len().checked_sub(1) != Some(n) {
+        return Err(invalid_matrix(&format!(
+            "expected {} indptr values, found {}",
+            n as u128 + 1,
+            indptr.len()
+        )));
+    }
+    let decreasing = indptr.windows(2).any(|pair| pair[0] > pair[1]);
+    if indptr[0] != 0 || decreasing || indptr[n] as usize != indices.len() {
+        return Err(invalid_matrix(&format!(
+            "indptr must increase from 0 to {}",
+            indices.len()
+        )));
+    }
+    check_values_length(indices.len(), values)?;
+    let mut entries = Vec::with_capacity(indices.len());
+    for row in 0..n {
+        for entry in indptr[row] as usize..indptr[row + 1] as usize {
+            let column = check_index(n, indices[entry], "column")?;
+            entries.push((row, column, values[entry]));
+        }
+    }
+    Ok(entries)
+}
+
+/// Create a graph of `n` nodes with `null` weights and an edge with the
+/// value as weight for every entry.
+pub fn entries_graph(n: usize, entries: &[MatrixEntry]) -> DiGraph {
+    let mut graph = graph::DiGraph::with_capacity(n, entries.len());
+    for _ in 0..n {
+        graph.add_node(JsValue::NULL);
+    }
+    for &(row, column, value) in entries {
+        graph.add_edge(
+            NodeIndex::new(row),
+            NodeIndex::new(column),
+            JsValue::from_f64(value),
+        );
+    }
+    DiGraph::from(graph)
+}
+
+/// How edge weights are turned into matrix values.
+enum WeightValue {
+    /// Number weights are used as is and other weights count as 1.
+    Default,
+    Property(JsValue, String),
+    Function(Function),
+}
+
+impl WeightValue {
+    fn from_options(options: &JsValue) -> Result<Self, JsValue> {
+        match get_option(options, "weight")? {
+            None => Ok(WeightValue::Default),
+            Some(weight) if weight.is_function() => Ok(WeightValue::Function(weight.into())),
+            Some(_) => {
+                let property = get_string_option(options, "weight")?.unwrap_or_default();
+                Ok(WeightValue::Property(
+                    JsValue::from_str(&property),
+                    property,
+                ))
+            }
+        }
+    }
+
+    fn value(&self, weight: &JsValue, index: usize) -> Result<f64, JsValue> {
+        let value = match self {
+            WeightValue::Default => return Ok(weight.as_f64().unwrap_or(1.0)),
+            WeightValue::Property(key, _) if weight.is_object() => Reflect::get(weight, key)?,
+            WeightValue::Property(..) => JsValue::UNDEFINED,
+            WeightValue::Function(function) => {
+                function.call2(&JsValue::NULL, weight, &JsValue::from(index as u32))?
+            }
+        };
+        value.as_f64().ok_or_else(|| {
+            let message = match self {
+                WeightValue::Property(_, property) => {
+                    format!("Edge weight is missing a number `{}` property", property)
+                }
+                _ => String::from("Option `weight` must return a number"),
+            };
+            GraphError::new(&message, GraphItemType::Edge, index as u32).to_js_value()
+        })
+    }
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) -> Result<(), JsValue> {
+    Reflect::set(object, &JsValue::from_str(key), value).map(|_| ())
+}
+
+fn get_array<T>(
+    matrix: &JsValue,
+    key: &str,
+    convert: impl Fn(&JsValue, &str) -> Result<T, JsValue>,
+) -> Result<T, JsValue> {
+    let value = Reflect::get(matrix, &JsValue::from_str(key))?;
+    if value.is_object() {
+        convert(&value, key)
+    } else {
+        Err(invalid_matrix(&format!("`{}` must be an array", key)).to_js_value())
+    }
+}
+
+/// Indices from a `Uint32Array`, or from any other array whose values are
+/// checked to be integers in the range of a `u32` instead of wrapping them.
+fn u32_array(value: &JsValue, key: &str) -> Result<Vec<u32>, JsValue> {
+    if let Some(array) = value.dyn_ref::<Uint32Array>() {
+        return Ok(array.to_vec());
+    }
+    Array::from(value)
+        .iter()
+        .map(|item| match item.as_f64() {
+            Some(index) if index >= 0.0 && index <= u32::MAX as f64 && index.fract() == 0.0 => {
+                Ok(index as u32)
+            }
+            _ => Err(
+                invalid_matrix(&format!("`{}` must only hold non-negative integers", key))
+                    .to_js_value(),
+            ),
+        })
+        .collect()
+}
+
+fn f64_array(value: &JsValue, _key: &str) -> Result<Vec<f64>, JsValue> {
+    Ok(Float64Array::new(value).to_vec())
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Get the adjacency matrix of the graph, with a row and a column for
+    /// every node. Parallel edges are summed. Options:
+    ///
+    /// * `weight` - the value of an edge. Either the name of a number
+    ///   property of edge weights, or a function called as
+    ///   `weight(weight, index)` returning a number. Defaults to edge weights
+    ///   which are numbers, and 1 for others.
+    /// * `dense` - return `{ data, shape }` with `data` a row-major
+    ///   `Float64Array` of `shape[0] * shape[1]` values. Defaults to `false`.
+    ///   Graphs with more than 4096 nodes throw a `GraphError`.
+    /// * `sparseFormat` - the format of sparse matrices, either `"coo"` (the
+    ///   default) for `{ rows, columns, values, shape }` or `"csr"` for
+    ///   `{ indptr, indices, values, shape }`. Indices are `Uint32Array`s and
+    ///   values a `Float64Array`, sorted by row and column.
+    #[wasm_bindgen(js_name = toAdjacencyMatrix)]
+    pub fn to_adjacency_matrix(&self, options: JsValue) -> Result<Object, JsValue> {
+        let weight_value = WeightValue::from_options(&options)?;
+        let dense = get_bool_option(&options, "dense", false)?;
+        let sparse_format = get_string_option(&options, "sparseFormat")?;
+        let mut edges = Vec::with_capacity(self.graph.edge_count());
+        for (index, edge) in self.graph.raw_edges().iter().enumerate() {
+            let value = weight_value.value(&edge.weight, index)?;
+            edges.push((edge.source().index(), edge.target().index(), value));
+        }
+        let entries = matrix_entries(edges.into_iter());
+        let n = self.graph.node_count();
+
+        let matrix = Object::new();
+        let shape = Array::of2(&JsValue::from(n as u32), &JsValue::from(n as u32));
+        set(&matrix, "shape", &shape)?;
+        if dense {
+            let data = dense_matrix(n, &entries).map_err(|err| err.to_js_value())?;
+            set(&matrix, "data", &Float64Array::from(&data[..]))?;
+            return Ok(matrix);
+        }
+        let values: Vec<f64> = entries.iter().map(|&(_, _, value)| value).collect();
+        match sparse_format.as_deref() {
+            None | Some("coo") => {
+                let rows: Vec<u32> = entries.iter().map(|&(row, _, _)| row as u32).collect();
+                let columns: Vec<u32> = entries.iter().map(|&(_, col, _)| col as u32).collect();
+                set(&matrix, "rows", &Uint32Array::from(&rows[..]))?;
+                set(&matrix, "columns", &Uint32Array::from(&columns[..]))?;
+            }
+            Some("csr") => {
+                let (indptr, indices, _) = csr_matrix(n, &entries);
+                set(&matrix, "indptr", &Uint32Array::from(&indptr[..]))?;
+                set(&matrix, "indices", &Uint32Array::from(&indices[..]))?;
+            }
+            Some(_) => {
+                return Err(GraphError::from_message(
+                    "Option `sparseFormat` must be \"coo\" or \"csr\"",
+                )
+                .to_js_value())
+            }
+        }
+        set(&matrix, "values", &Float64Array::from(&values[..]))?;
+        Ok(matrix)
+    }
+
+    /// Create a graph from an adjacency matrix in any of the forms returned
+    /// by `toAdjacencyMatrix`, where `shape` must be square. The graph has a
+    /// node with a `null` weight for every row and an edge for every
+    /// non-zero value of a dense matrix or every entry of a sparse one, with
+    /// the value as weight. Arrays may be typed arrays or plain arrays of
+    /// numbers, where `shape` and indices must be non-negative integers.
+    /// Matrices may have at most 16777216 rows.
+    #[wasm_bindgen(js_name = fromAdjacencyMatrix)]
+    pub fn from_adjacency_matrix(matrix: JsValue) -> Result<DiGraph, JsValue> {
+        let shape = get_array(&matrix, "shape", u32_array)?;
+        let n = match shape[..] {
+            [rows, columns] if rows == columns => rows as usize,
+            _ => return Err(invalid_matrix("`shape` must be square").to_js_value()),
+        };
+        if n > MAX_NODE_COUNT {
+            return Err(invalid_matrix(&format!("too many nodes ({})", n)).to_js_value());
+        }
+        let has = |key: &str| Reflect::has(&matrix, &JsValue::from_str(key)).unwrap_or(false);
+        let entries = if has("data") {
+            dense_entries(n, &get_array(&matrix, "data", f64_array)?)
+        } else if has("indptr") {
+            csr_entries(
+                n,
+                &get_array(&matrix, "indptr", u32_array)?,
+                &get_array(&matrix, "indices", u32_array)?,
+                &get_array(&matrix, "values", f64_array)?,
+            )
+        } else {
+            coo_entries(
+                n,
+                &get_array(&matrix, "rows", u32_array)?,
+                &get_array(&matrix, "columns", u32_array)?,
+                &get_array(&matrix, "values", f64_array)?,
+            )
+        };
+        entries
+            .map(|entries| entries_graph(n, &entries))
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use wasm_bindgen_test::*;
+
+    #[test]
+    fn can_build_matrices() {
+        let entries = matrix_entries(vec![(1, 0, 2.0), (0, 2, 1.0), (1, 0, 0.5)].into_iter());
+        assert_eq!(entries, vec![(0, 2, 1.0), (1, 0, 2.5)]);
+        assert_eq!(
+            dense_matrix(3, &entries).unwrap(),
+            vec![0.0, 0.0, 1.0, 2.5, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            csr_matrix(3, &entries),
+            (vec![0, 1, 2, 2], vec![2, 0], vec![1.0, 2.5])
+        );
+    }
+
+    #[test]
+    fn can_read_matrices() {
+        let entries = vec![(0, 2, 1.0), (1, 0, 2.5)];
+        let dense = dense_matrix(3, &entries).unwrap();
+        assert_eq!(dense_entries(3, &dense).unwrap(), entries);
+        assert_eq!(
+            csr_entries(3, &[0, 1, 2, 2], &[2, 0], &[1.0, 2.5]).unwrap(),
+            entries
+        );
+        assert_eq!(
+            coo_entries(3, &[0, 1], &[2, 0], &[1.0, 2.5]).unwrap(),
+            entries
+        );
+    }
+
+    #[test]
+    fn can_reject_large_dense_matrices() {
+        assert_eq!(dense_matrix(4096, &[]).unwrap().len(), MAX_DENSE_VALUES);
+        for &n in &[4097, 1 << 16, usize::MAX] {
+            assert_eq!(
+                dense_matrix(n, &[]).unwrap_err(),
+                GraphError::from_message(&format!(
+                    "Too many nodes ({}) for a dense adjacency matrix, use a sparse one",
+                    n
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn can_report_invalid_matrices() {
+        assert_eq!(
+            dense_entries(2, &[1.0]).unwrap_err(),
+            invalid_matrix("expected 4 values for shape [2, 2], found 1")
+        );
+        assert_eq!(
+            coo_entries(2, &[0], &[2], &[1.0]).unwrap_err(),
+            invalid_matrix("column index 2 is out of bounds for 2 nodes")
+        );
+        assert_eq!(
+            csr_entries(2, &[0, 2, 1], &[0], &[1.0]).unwrap_err(),
+            invalid_matrix("indptr must increase from 0 to 1")
+        );
+        assert_eq!(
+            csr_entries(usize::MAX, &[0], &[], &[]).unwrap_err(),
+            invalid_matrix(&format!(
+                "expected {} indptr values, found 1",
+                usize::MAX as u128 + 1
+            ))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_adjacency_matrix() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let matrix = g.to_adjacency_matrix(JsValue::UNDEFINED).unwrap();
+        assert!(Reflect::has(&matrix, &JsValue::from_str("rows")).unwrap());
+        let imported = DiGraph::from_adjacency_matrix(matrix.into()).unwrap();
+        assert_eq!(imported.node_count(), 5);
+        assert_eq!(imported.edge_count(), 9);
+        let options = Object::new();
+        set(&options, "dense", &JsValue::TRUE).unwrap();
+        let matrix = g.to_adjacency_matrix(options.into()).unwrap();
+        let imported = DiGraph::from_adjacency_matrix(matrix.into()).unwrap();
+        assert_eq!(imported.edge_count(), 9);
+        let options = Object::new();
+        set(&options, "sparseFormat", &JsValue::from_str("csr")).unwrap();
+        let matrix = g.to_adjacency_matrix(options.into()).unwrap();
+        let imported = DiGraph::from_adjacency_matrix(matrix.into()).unwrap();
+        assert_eq!(imported.edge_count(), 9);
+        assert_eq!(imported.edge_weight(0).unwrap(), JsValue::from_f64(1.0));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_invalid_plain_arrays() {
+        let matrix = |shape: Array, rows: Array| {
+            let matrix = Object::new();
+            set(&matrix, "shape", &shape).unwrap();
+            set(&matrix, "rows", &rows).unwrap();
+            set(&matrix, "columns", &Array::of1(&JsValue::from(0))).unwrap();
+            set(&matrix, "values", &Array::of1(&JsValue::from(1))).unwrap();
+            DiGraph::from_adjacency_matrix(matrix.into())
+        };
+        let two = || Array::of2(&JsValue::from(2), &JsValue::from(2));
+        assert!(matrix(two(), Array::of1(&JsValue::from(1))).is_ok());
+        for row in &[-1.0, 1.5, f64::NAN] {
+            assert!(matrix(two(), Array::of1(&JsValue::from_f64(*row))).is_err());
+        }
+        let huge = JsValue::from(u32::MAX);
+        assert!(matrix(Array::of2(&huge, &huge), Array::of1(&JsValue::from(0))).is_err());
+    }
+}
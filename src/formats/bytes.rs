@@ -0,0 +1,488 @@
+//! Compact, versioned binary encoding of a `DiGraph` for saving and
+//! transferring graphs without going through text.
+//!
+//! All integers are little endian. The layout of version 1 is:
+//!
+//! * the magic bytes `PGWB`, the format version, the weight encoding, the
+//!   policy flags and a reserved zero byte, one byte each after the magic,
+//! * node count and edge count as `u32`,
+//! * source and target node index of every edge as `u32`,
+//! * the weights of all nodes and then all edges, each as a `u32` length
+//!   followed by the encoded weight.
+
+use crate::formats::json::{json_to_js, JsonGraph};
+use crate::graph_impl::{DiGraph, GraphPolicy};
+use crate::js_helpers::options::get_string_option;
+use crate::GraphError;
+use js_sys::JSON;
+use petgraph::algo::toposort;
+use petgraph::graph::{self, NodeIndex};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::convert::TryInto;
+use wasm_bindgen::prelude::*;
+
+pub const MAGIC: &[u8; 4] = b"PGWB";
+pub const VERSION: u8 = 1;
+
+const ALLOW_PARALLEL_EDGES: u8 = 1;
+const ALLOW_SELF_LOOPS: u8 = 1 << 1;
+const ACYCLIC: u8 = 1 << 2;
+
+/// Serializer used for weights, stored in the header by its tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightEncoding {
+    Json = 1,
+    MessagePack = 2,
+}
+
+impl WeightEncoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(WeightEncoding::Json),
+            "messagepack" => Some(WeightEncoding::MessagePack),
+            _ => None,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(WeightEncoding::Json),
+            2 => Some(WeightEncoding::MessagePack),
+            _ => None,
+        }
+    }
+
+    pub fn encode(self, weight: &Value) -> Result<Vec<u8>, String> {
+        match self {
+            WeightEncoding::Json => serde_json::to_vec(weight).map_err(|err| err.to_string()),
+            WeightEncoding::MessagePack => rmp_serde::to_vec(weight).map_err(|err| err.to_string()),
+        }
+    }
+
+    /// Encode a JS weight without first converting the graph to JSON values.
+    /// JSON is written as given by `JSON.stringify`, where `undefined`
+    /// becomes `null` as in `into_serde`.
+    pub fn encode_js(self, weight: &JsValue) -> Result<Vec<u8>, String> {
+        match self {
+            WeightEncoding::Json => {
+                let text =
+                    JSON::stringify(weight).map_err(|_| String::from("not JSON compatible"))?;
+                Ok(text
+                    .as_string()
+                    .unwrap_or_else(|| String::from("null"))
+                    .into_bytes())
+            }
+            WeightEncoding::MessagePack => {
+                let value: Value = weight.into_serde().map_err(|err| err.to_string())?;
+                self.encode(&value)
+            }
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Result<Value, String> {
+        match self {
+            WeightEncoding::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+            WeightEncoding::MessagePack => {
+                rmp_serde::from_slice(bytes).map_err(|err| err.to_string())
+            }
+        }
+    }
+
+    /// Decode a weight straight to a JS value. JSON is read by `JSON.parse`.
+    pub fn decode_js(self, bytes: &[u8]) -> Result<JsValue, String> {
+        match self {
+            WeightEncoding::Json => {
+                let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+                JSON::parse(text).map_err(|_| String::from("invalid JSON"))
+            }
+            WeightEncoding::MessagePack => self.decode(bytes).map(json_to_js),
+        }
+    }
+}
+
+fn invalid_bytes(message: &str) -> GraphError {
+    GraphError::from_message(&format!("Invalid graph bytes: {}", message))
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: usize) -> Result<(), GraphError> {
+    let value: u32 = value
+        .try_into()
+        .map_err(|_| GraphError::from_message("Graph is too large to encode"))?;
+    bytes.extend_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+/// Encode a graph and its policy.
+pub fn encode_graph(
+    graph: &JsonGraph,
+    policy: GraphPolicy,
+    encoding: WeightEncoding,
+) -> Result<Vec<u8>, GraphError> {
+    encode_graph_with(graph, policy, encoding, |weight| encoding.encode(weight))
+}
+
+/// Encode a graph and its policy, with `encode_weight` giving the bytes of a
+/// weight in `encoding`.
+pub fn encode_graph_with<W>(
+    graph: &graph::DiGraph<W, W>,
+    policy: GraphPolicy,
+    encoding: WeightEncoding,
+    mut encode_weight: impl FnMut(&W) -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, GraphError> {
+    let mut flags = 0;
+    if policy.allow_parallel_edges {
+        flags |= ALLOW_PARALLEL_EDGES;
+    }
+    if policy.allow_self_loops {
+        flags |= ALLOW_SELF_LOOPS;
+    }
+    if policy.acyclic {
+        flags |= ACYCLIC;
+    }
+    let mut bytes = Vec::with_capacity(16 + 8 * graph.edge_count());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[VERSION, encoding as u8, flags, 0]);
+    push_u32(&mut bytes, graph.node_count())?;
+    push_u32(&mut bytes, graph.edge_count())?;
+    for edge in graph.raw_edges() {
+        push_u32(&mut bytes, edge.source().index())?;
+        push_u32(&mut bytes, edge.target().index())?;
+    }
+    let weights = graph
+        .raw_nodes()
+        .iter()
+        .map(|node| &node.weight)
+        .chain(graph.raw_edges().iter().map(|edge| &edge.weight));
+    for weight in weights {
+        let encoded = encode_weight(weight).map_err(|err| {
+            GraphError::from_message(&format!("Failed to encode weight: {}", err))
+        })?;
+        push_u32(&mut bytes, encoded.len())?;
+        bytes.extend_from_slice(&encoded);
+    }
+    Ok(bytes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], GraphError> {
+        if self.bytes.len() - self.position < len {
+            return Err(invalid_bytes("unexpected end of data"));
+        }
+        let taken = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        Ok(taken)
+    }
+
+    fn read_u32(&mut self) -> Result<usize, GraphError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+}
+
+/// Decode a graph and its policy encoded by `encode_graph`.
+pub fn decode_graph(bytes: &[u8]) -> Result<(JsonGraph, GraphPolicy), GraphError> {
+    decode_graph_with(bytes, WeightEncoding::decode)
+}
+
+/// Decode a graph and its policy, with `decode_weight` reading a weight from
+/// its bytes in the given encoding.
+pub fn decode_graph_with<W>(
+    bytes: &[u8],
+    mut decode_weight: impl FnMut(WeightEncoding, &[u8]) -> Result<W, String>,
+) -> Result<(graph::DiGraph<W, W>, GraphPolicy), GraphError> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err(invalid_bytes("missing header"));
+    }
+    let header = reader.take(4)?;
+    if header[0] != VERSION {
+        return Err(invalid_bytes(&format!("unsupported version {}", header[0])));
+    }
+    let encoding = WeightEncoding::from_tag(header[1])
+        .ok_or_else(|| invalid_bytes(&format!("unknown weight encoding {}", header[1])))?;
+    let flags = header[2];
+    if flags & !(ALLOW_PARALLEL_EDGES | ALLOW_SELF_LOOPS | ACYCLIC) != 0 {
+        return Err(invalid_bytes(&format!(
+            "unknown policy flags {:#010b}",
+            flags
+        )));
+    }
+    if header[3] != 0 {
+        return Err(invalid_bytes("reserved header byte must be 0"));
+    }
+    let policy = GraphPolicy {
+        allow_parallel_edges: flags & ALLOW_PARALLEL_EDGES != 0,
+        allow_self_loops: flags & ALLOW_SELF_LOOPS != 0,
+        acyclic: flags & ACYCLIC != 0,
+    };
+    let node_count = reader.read_u32()?;
+    let edge_count = reader.read_u32()?;
+
+    // Don't trust the counts for allocation before the data is known to be
+    // there, every edge takes 8 bytes and every weight at least 4.
+    let mut endpoints = Vec::with_capacity(edge_count.min(reader.remaining() / 8));
+    for index in 0..edge_count {
+        let (source, target) = (reader.read_u32()?, reader.read_u32()?);
+        if source >= node_count || target >= node_count {
+            return Err(invalid_bytes(&format!(
+                "edge {} references a node out of bounds",
+                index
+            )));
+        }
+        if source == target && !policy.allow_self_loops {
+            return Err(invalid_bytes(&format!(
+                "edge {} is a self loop but the policy doesn't allow them",
+                index
+            )));
+        }
+        endpoints.push((NodeIndex::new(source), NodeIndex::new(target)));
+    }
+    if !policy.allow_parallel_edges {
+        let mut seen = HashSet::with_capacity(endpoints.len());
+        for (index, pair) in endpoints.iter().enumerate() {
+            if !seen.insert(pair) {
+                return Err(invalid_bytes(&format!(
+                    "edge {} is a parallel edge but the policy doesn't allow them",
+                    index
+                )));
+            }
+        }
+    }
+    let mut read_weight = |reader: &mut Reader| -> Result<W, GraphError> {
+        let len = reader.read_u32()?;
+        decode_weight(encoding, reader.take(len)?)
+            .map_err(|err| invalid_bytes(&format!("failed to decode weight: {}", err)))
+    };
+    let mut graph =
+        graph::DiGraph::with_capacity(node_count.min(reader.remaining() / 4), endpoints.len());
+    for _ in 0..node_count {
+        graph.add_node(read_weight(&mut reader)?);
+    }
+    for (source, target) in endpoints {
+        graph.add_edge(source, target, read_weight(&mut reader)?);
+    }
+    if reader.remaining() != 0 {
+        return Err(invalid_bytes("unexpected data after the graph"));
+    }
+    // `toposort` is iterative, unlike `is_cyclic_directed` which recurses
+    // along every path and overflows the stack on long chains.
+    if policy.acyclic && toposort(&graph, None).is_err() {
+        return Err(invalid_bytes(
+            "edges form a cycle but the policy is acyclic",
+        ));
+    }
+    Ok((graph, policy))
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Encode the graph, including its policy, in a compact binary format
+    /// which `fromBytes` reads back with identical indices. Options:
+    ///
+    /// * `weights` - weight serializer, `"json"` (the default) or
+    ///   `"messagepack"`. Weights must be JSON compatible either way.
+    ///
+    /// The result can be stored in IndexedDB or transferred to a worker with
+    /// `postMessage(bytes, [bytes.buffer])`.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self, options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let encoding = match get_string_option(&options, "weights")? {
+            None => WeightEncoding::Json,
+            Some(name) => WeightEncoding::from_name(&name).ok_or_else(|| {
+                GraphError::from_message("Option `weights` must be \"json\" or \"messagepack\"")
+                    .to_js_value()
+            })?,
+        };
+        encode_graph_with(&self.graph, self.policy, encoding, |weight| {
+            encoding.encode_js(weight)
+        })
+        .map_err(|err| err.to_js_value())
+    }
+
+    /// Create a graph from the result of `toBytes`.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<DiGraph, JsValue> {
+        let (graph, policy) =
+            decode_graph_with(bytes, WeightEncoding::decode_js).map_err(|err| err.to_js_value())?;
+        let mut graph = DiGraph::from(graph);
+        graph.policy = policy;
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    fn new_json_graph() -> JsonGraph {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({ "name": "a", "tags": [1, 2.5, null] }));
+        let b = graph.add_node(Value::Null);
+        graph.add_edge(a, b, json!(-3));
+        graph.add_edge(b, b, json!("loop"));
+        graph
+    }
+
+    fn assert_same_graph(a: &JsonGraph, b: &JsonGraph) {
+        let weights = |g: &JsonGraph| -> Vec<Value> {
+            g.raw_nodes()
+                .iter()
+                .map(|n| n.weight.clone())
+                .chain(g.raw_edges().iter().map(|e| e.weight.clone()))
+                .collect()
+        };
+        let endpoints = |g: &JsonGraph| -> Vec<(usize, usize)> {
+            g.raw_edges()
+                .iter()
+                .map(|e| (e.source().index(), e.target().index()))
+                .collect()
+        };
+        assert_eq!(weights(a), weights(b));
+        assert_eq!(endpoints(a), endpoints(b));
+    }
+
+    #[test]
+    fn can_encode_graph_header() {
+        let policy = GraphPolicy {
+            acyclic: true,
+            ..GraphPolicy::default()
+        };
+        let bytes = encode_graph(&new_json_graph(), policy, WeightEncoding::Json).unwrap();
+        assert_eq!(
+            &bytes[..24],
+            &[
+                b'P', b'G', b'W', b'B', 1, 1, 0b111, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 0,
+                0, 0
+            ]
+        );
+    }
+
+    #[test]
+    fn can_round_trip_bytes() {
+        let graph = new_json_graph();
+        for encoding in &[WeightEncoding::Json, WeightEncoding::MessagePack] {
+            let policy = GraphPolicy {
+                allow_parallel_edges: false,
+                ..GraphPolicy::default()
+            };
+            let bytes = encode_graph(&graph, policy, *encoding).unwrap();
+            let (decoded, decoded_policy) = decode_graph(&bytes).unwrap();
+            assert_same_graph(&graph, &decoded);
+            assert_eq!(decoded_policy, policy);
+        }
+    }
+
+    #[test]
+    fn can_reject_invalid_bytes() {
+        let bytes = encode_graph(
+            &new_json_graph(),
+            GraphPolicy::default(),
+            WeightEncoding::MessagePack,
+        )
+        .unwrap();
+        let error = |bytes: &[u8]| decode_graph(bytes).unwrap_err();
+        assert_eq!(error(b"PGW"), invalid_bytes("unexpected end of data"));
+        assert_eq!(error(b"JSON{}{}"), invalid_bytes("missing header"));
+        let mut future = bytes.clone();
+        future[4] = 9;
+        assert_eq!(error(&future), invalid_bytes("unsupported version 9"));
+        let mut out_of_bounds = bytes.clone();
+        out_of_bounds[20] = 2;
+        assert_eq!(
+            error(&out_of_bounds),
+            invalid_bytes("edge 0 references a node out of bounds")
+        );
+        assert_eq!(
+            error(&bytes[..bytes.len() - 1]),
+            invalid_bytes("unexpected end of data")
+        );
+        let mut reserved = bytes.clone();
+        reserved[7] = 1;
+        assert_eq!(
+            error(&reserved),
+            invalid_bytes("reserved header byte must be 0")
+        );
+        let mut unknown_flags = bytes.clone();
+        unknown_flags[6] |= 1 << 5;
+        assert_eq!(
+            error(&unknown_flags),
+            invalid_bytes("unknown policy flags 0b00100011")
+        );
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(
+            error(&trailing),
+            invalid_bytes("unexpected data after the graph")
+        );
+    }
+
+    #[test]
+    fn can_reject_policy_violations() {
+        let mut graph = new_json_graph();
+        let error = |graph: &JsonGraph, flags: u8| {
+            let mut bytes =
+                encode_graph(graph, GraphPolicy::default(), WeightEncoding::Json).unwrap();
+            bytes[6] = flags;
+            decode_graph(&bytes).unwrap_err()
+        };
+        assert_eq!(
+            error(&graph, ALLOW_PARALLEL_EDGES),
+            invalid_bytes("edge 1 is a self loop but the policy doesn't allow them")
+        );
+        assert_eq!(
+            error(&graph, ALLOW_PARALLEL_EDGES | ALLOW_SELF_LOOPS | ACYCLIC),
+            invalid_bytes("edges form a cycle but the policy is acyclic")
+        );
+        graph.add_edge(NodeIndex::new(0), NodeIndex::new(1), Value::Null);
+        assert_eq!(
+            error(&graph, ALLOW_SELF_LOOPS),
+            invalid_bytes("edge 2 is a parallel edge but the policy doesn't allow them")
+        );
+    }
+
+    #[test]
+    fn can_decode_long_acyclic_chain() {
+        let mut graph = JsonGraph::new();
+        let mut previous = graph.add_node(Value::Null);
+        for _ in 0..1_000_000 {
+            let node = graph.add_node(Value::Null);
+            graph.add_edge(previous, node, Value::Null);
+            previous = node;
+        }
+        let policy = GraphPolicy {
+            acyclic: true,
+            ..GraphPolicy::default()
+        };
+        let bytes = encode_graph(&graph, policy, WeightEncoding::Json).unwrap();
+        let (decoded, _policy) = decode_graph(&bytes).unwrap();
+        assert_eq!(decoded.edge_count(), 1_000_000);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_bytes_js() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let bytes = g.to_bytes(JsValue::UNDEFINED).unwrap();
+        let decoded = DiGraph::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.node_weights(), g.node_weights());
+        assert_eq!(decoded.edge_endpoints_all(), g.edge_endpoints_all());
+        let options = js_sys::Object::new();
+        let encoding = JsValue::from_str("messagepack");
+        js_sys::Reflect::set(&options, &JsValue::from_str("weights"), &encoding).unwrap();
+        let bytes = g.to_bytes(options.into()).unwrap();
+        let decoded = DiGraph::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.node_weights(), g.node_weights());
+    }
+}
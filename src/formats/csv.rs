@@ -0,0 +1,987 @@
+//! Delimited text (CSV, TSV, ...) edge lists and adjacency lists. Parsers take
+//! the text in chunks, so large files don't have to be held as one string, and
+//! writers produce the text in chunks of rows.
+//!
+//! Errors are located by row and column, where the row is the 1-based line a
+//! record starts on and the column is the 1-based field number.
+
+use crate::formats::json::{json_to_js, JsonGraph};
+use crate::formats::{id_text, node_ids};
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::{
+    get_bool_option, get_function_option, get_string_array_option, get_string_option,
+};
+use crate::{GraphError, GraphItemType};
+use petgraph::graph::{self, NodeIndex};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Number of rows written per chunk.
+const CHUNK_ROWS: usize = 1024;
+
+/// A record of delimited text with the row it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub row: u32,
+    pub fields: Vec<String>,
+}
+
+/// Splits delimited text into records. Fields may be quoted with `"`, where
+/// `""` is an escaped quote and delimiters and line breaks are kept. Empty
+/// lines are skipped.
+#[derive(Debug, Clone)]
+pub struct RecordReader {
+    delimiter: char,
+    field: String,
+    fields: Vec<String>,
+    in_quotes: bool,
+    /// A quote was read in a quoted field, which either ends the field or is
+    /// the first half of an escaped quote.
+    quote_pending: bool,
+    line: u32,
+    record_row: u32,
+}
+
+impl RecordReader {
+    pub fn new(delimiter: char) -> Self {
+        RecordReader {
+            delimiter,
+            field: String::new(),
+            fields: Vec::new(),
+            in_quotes: false,
+            quote_pending: false,
+            line: 1,
+            record_row: 1,
+        }
+    }
+
+    fn end_record(&mut self, records: &mut Vec<Record>) {
+        let field = std::mem::take(&mut self.field);
+        let mut fields = std::mem::take(&mut self.fields);
+        if !fields.is_empty() || !field.is_empty() {
+            fields.push(field);
+            records.push(Record {
+                row: self.record_row,
+                fields,
+            });
+        }
+    }
+
+    /// Read a chunk of text, adding all records completed by it.
+    pub fn push(&mut self, chunk: &str, records: &mut Vec<Record>) {
+        for c in chunk.chars() {
+            if self.in_quotes {
+                if !self.quote_pending {
+                    match c {
+                        '"' => self.quote_pending = true,
+                        '\n' => {
+                            self.line += 1;
+                            self.field.push(c);
+                        }
+                        _ => self.field.push(c),
+                    }
+                    continue;
+                }
+                self.quote_pending = false;
+                if c == '"' {
+                    self.field.push(c);
+                    continue;
+                }
+                self.in_quotes = false;
+            }
+            match c {
+                '\n' => {
+                    self.end_record(records);
+                    self.line += 1;
+                    self.record_row = self.line;
+                }
+                '\r' => {}
+                '"' if self.field.is_empty() => self.in_quotes = true,
+                c if c == self.delimiter => self.fields.push(std::mem::take(&mut self.field)),
+                c => self.field.push(c),
+            }
+        }
+    }
+
+    /// Add the last record, which doesn't need to end with a line break.
+    pub fn finish(&mut self, records: &mut Vec<Record>) -> Result<(), GraphError> {
+        if self.in_quotes && !self.quote_pending {
+            return Err(GraphError::from_message("Unterminated quoted field")
+                .with_location(self.record_row, self.fields.len() as u32 + 1));
+        }
+        self.in_quotes = false;
+        self.quote_pending = false;
+        self.end_record(records);
+        Ok(())
+    }
+}
+
+/// Nodes created on first use of their key, with the key as weight.
+#[derive(Debug, Clone, Default)]
+pub struct KeyedNodes {
+    indices: HashMap<String, NodeIndex>,
+}
+
+impl KeyedNodes {
+    pub fn node<W>(
+        &mut self,
+        graph: &mut graph::DiGraph<W, W>,
+        key: &str,
+        weight: fn(Value) -> W,
+    ) -> NodeIndex {
+        if let Some(&index) = self.indices.get(key) {
+            return index;
+        }
+        let index = graph.add_node(weight(Value::from(key)));
+        self.indices.insert(String::from(key), index);
+        index
+    }
+}
+
+fn cell_value(text: &str, parse_numbers: bool) -> Value {
+    if parse_numbers {
+        let trimmed = text.trim();
+        if let Ok(number) = trimmed.parse::<i64>() {
+            return Value::from(number);
+        }
+        if let Ok(number) = trimmed.parse::<f64>() {
+            if number.is_finite() {
+                return Value::from(number);
+            }
+        }
+    }
+    Value::from(text)
+}
+
+fn invalid_row(message: &str, row: u32, column: usize) -> GraphError {
+    GraphError::from_message(&format!("Invalid edge list: {}", message))
+        .with_location(row, column as u32 + 1)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeListOptions {
+    pub delimiter: char,
+    /// Whether the first record holds the column names.
+    pub header: bool,
+    /// Column names when there is no header, defaults to `0`, `1`, ....
+    pub columns: Option<Vec<String>>,
+    /// Defaults to the `source` column if there is one, otherwise the first.
+    pub source_column: Option<String>,
+    /// Defaults to the `target` column if there is one, otherwise the second.
+    pub target_column: Option<String>,
+    /// Columns stored in the edge weights, defaults to all other columns.
+    pub weight_columns: Option<Vec<String>>,
+    /// Store numeric cells of weight columns as numbers instead of strings.
+    /// Node keys are always kept as written, so `007` and `7` differ.
+    pub parse_numbers: bool,
+}
+
+impl Default for EdgeListOptions {
+    fn default() -> Self {
+        EdgeListOptions {
+            delimiter: ',',
+            header: true,
+            columns: None,
+            source_column: None,
+            target_column: None,
+            weight_columns: None,
+            parse_numbers: false,
+        }
+    }
+}
+
+/// Column positions of an edge list.
+#[derive(Debug, Clone)]
+struct EdgeListLayout {
+    source: usize,
+    target: usize,
+    weights: Vec<(usize, String)>,
+}
+
+impl EdgeListLayout {
+    fn new(names: &[String], options: &EdgeListOptions, row: u32) -> Result<Self, GraphError> {
+        let find = |name: &str| {
+            names
+                .iter()
+                .position(|column| column == name)
+                .ok_or_else(|| invalid_row(&format!("unknown column `{}`", name), row, 0))
+        };
+        let find_or = |option: &Option<String>, name: &str, default: usize| match option {
+            Some(column) => find(column),
+            None => Ok(names
+                .iter()
+                .position(|column| column == name)
+                .unwrap_or(default)),
+        };
+        if names.len() < 2 {
+            return Err(invalid_row("expected at least 2 columns", row, 0));
+        }
+        let source = find_or(&options.source_column, "source", 0)?;
+        let target = find_or(&options.target_column, "target", 1)?;
+        if source == target {
+            return Err(invalid_row(
+                "source and target are the same column",
+                row,
+                source,
+            ));
+        }
+        let weights = match &options.weight_columns {
+            Some(columns) => columns
+                .iter()
+                .map(|name| Ok((find(name)?, name.clone())))
+                .collect::<Result<_, GraphError>>()?,
+            None => names
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != source && *index != target)
+                .map(|(index, name)| (index, name.clone()))
+                .collect(),
+        };
+        Ok(EdgeListLayout {
+            source,
+            target,
+            weights,
+        })
+    }
+}
+
+/// Incremental edge list parser. Nodes are created for every distinct key in
+/// the source and target columns with the key as weight, edge weights are
+/// objects of the non-empty weight columns. Weights are converted by
+/// `weight` as the rows are parsed.
+pub struct EdgeListParser<W> {
+    options: EdgeListOptions,
+    reader: RecordReader,
+    layout: Option<EdgeListLayout>,
+    graph: graph::DiGraph<W, W>,
+    nodes: KeyedNodes,
+    weight: fn(Value) -> W,
+}
+
+impl<W> EdgeListParser<W> {
+    pub fn new(options: EdgeListOptions, weight: fn(Value) -> W) -> Self {
+        EdgeListParser {
+            reader: RecordReader::new(options.delimiter),
+            options,
+            layout: None,
+            graph: graph::DiGraph::new(),
+            nodes: KeyedNodes::default(),
+            weight,
+        }
+    }
+
+    fn add_record(&mut self, record: Record) -> Result<(), GraphError> {
+        let layout = match &self.layout {
+            Some(layout) => layout,
+            None => {
+                if self.options.header {
+                    let layout = EdgeListLayout::new(&record.fields, &self.options, record.row)?;
+                    self.layout = Some(layout);
+                    return Ok(());
+                }
+                let names = match &self.options.columns {
+                    Some(columns) => columns.clone(),
+                    None => (0..record.fields.len()).map(|i| i.to_string()).collect(),
+                };
+                let layout = EdgeListLayout::new(&names, &self.options, record.row)?;
+                self.layout.get_or_insert(layout)
+            }
+        };
+        let cell = |index: usize| record.fields.get(index).map_or("", String::as_str);
+        for &(index, name) in &[(layout.source, "source"), (layout.target, "target")] {
+            if cell(index).is_empty() {
+                return Err(invalid_row(&format!("empty {}", name), record.row, index));
+            }
+        }
+        let mut weight = Map::new();
+        for (index, name) in &layout.weights {
+            let text = cell(*index);
+            if !text.is_empty() {
+                weight.insert(name.clone(), cell_value(text, self.options.parse_numbers));
+            }
+        }
+        let source = self
+            .nodes
+            .node(&mut self.graph, cell(layout.source), self.weight);
+        let target = self
+            .nodes
+            .node(&mut self.graph, cell(layout.target), self.weight);
+        self.graph
+            .add_edge(source, target, (self.weight)(Value::Object(weight)));
+        Ok(())
+    }
+
+    fn add_records(&mut self, records: Vec<Record>) -> Result<(), GraphError> {
+        for record in records {
+            self.add_record(record)?;
+        }
+        Ok(())
+    }
+
+    /// Parse a chunk of text.
+    pub fn push(&mut self, chunk: &str) -> Result<(), GraphError> {
+        let mut records = Vec::new();
+        self.reader.push(chunk, &mut records);
+        self.add_records(records)
+    }
+
+    /// Parse the rest of the text and get the graph.
+    pub fn finish(mut self) -> Result<graph::DiGraph<W, W>, GraphError> {
+        let mut records = Vec::new();
+        self.reader.finish(&mut records)?;
+        self.add_records(records)?;
+        Ok(self.graph)
+    }
+}
+
+/// Incremental adjacency list parser for records of a node key followed by
+/// the keys of its successors. Node weights are the keys and edge weights are
+/// `null`, converted by `weight`.
+pub struct AdjacencyListParser<W> {
+    reader: RecordReader,
+    graph: graph::DiGraph<W, W>,
+    nodes: KeyedNodes,
+    weight: fn(Value) -> W,
+}
+
+impl<W> AdjacencyListParser<W> {
+    pub fn new(delimiter: char, weight: fn(Value) -> W) -> Self {
+        AdjacencyListParser {
+            reader: RecordReader::new(delimiter),
+            graph: graph::DiGraph::new(),
+            nodes: KeyedNodes::default(),
+            weight,
+        }
+    }
+
+    fn add_records(&mut self, records: Vec<Record>) -> Result<(), GraphError> {
+        for record in records {
+            if record.fields[0].is_empty() {
+                return Err(
+                    GraphError::from_message("Invalid adjacency list: empty node")
+                        .with_location(record.row, 1),
+                );
+            }
+            let node = self
+                .nodes
+                .node(&mut self.graph, &record.fields[0], self.weight);
+            for key in record.fields[1..].iter().filter(|key| !key.is_empty()) {
+                let neighbor = self.nodes.node(&mut self.graph, key, self.weight);
+                self.graph
+                    .add_edge(node, neighbor, (self.weight)(Value::Null));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a chunk of text.
+    pub fn push(&mut self, chunk: &str) -> Result<(), GraphError> {
+        let mut records = Vec::new();
+        self.reader.push(chunk, &mut records);
+        self.add_records(records)
+    }
+
+    /// Parse the rest of the text and get the graph.
+    pub fn finish(mut self) -> Result<graph::DiGraph<W, W>, GraphError> {
+        let mut records = Vec::new();
+        self.reader.finish(&mut records)?;
+        self.add_records(records)?;
+        Ok(self.graph)
+    }
+}
+
+/// Keys identifying the nodes in delimited text. With a `key_property` they
+/// come from `node_ids`, otherwise string and number weights are used as
+/// keys and other nodes get their index.
+pub fn node_keys(graph: &JsonGraph, key_property: Option<&str>) -> Result<Vec<String>, GraphError> {
+    if key_property.is_some() {
+        return node_ids(graph, key_property);
+    }
+    let mut seen = HashMap::new();
+    let mut keys = Vec::with_capacity(graph.node_count());
+    for (index, node) in graph.raw_nodes().iter().enumerate() {
+        let key = id_text(Some(&node.weight)).unwrap_or_else(|| index.to_string());
+        if seen.insert(key.clone(), index).is_some() {
+            return Err(GraphError::new(
+                &format!("Duplicate node key `{}`", key),
+                GraphItemType::Node,
+                index as u32,
+            ));
+        }
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+fn push_field(line: &mut String, text: &str, delimiter: char) {
+    let needs_quotes = text
+        .chars()
+        .any(|c| c == delimiter || c == '"' || c == '\n' || c == '\r');
+    if needs_quotes {
+        line.push('"');
+        line.push_str(&text.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(text);
+    }
+}
+
+fn push_row<'a>(out: &mut String, fields: impl Iterator<Item = &'a str>, delimiter: char) {
+    for (index, field) in fields.enumerate() {
+        if index > 0 {
+            out.push(delimiter);
+        }
+        push_field(out, field, delimiter);
+    }
+    out.push('\n');
+}
+
+fn value_text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Write an edge list in chunks of rows. The columns after `source` and
+/// `target` are the properties of object weights in order of first
+/// appearance, other weights go in a `weight` column.
+pub fn write_edge_list<E>(
+    graph: &JsonGraph,
+    keys: &[String],
+    delimiter: char,
+    header: bool,
+    mut write: impl FnMut(&str) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut columns: Vec<&str> = Vec::new();
+    for edge in graph.raw_edges() {
+        let names: Vec<&str> = match &edge.weight {
+            Value::Null => Vec::new(),
+            Value::Object(object) => object.keys().map(String::as_str).collect(),
+            _ => vec!["weight"],
+        };
+        for name in names {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+    let mut out = String::new();
+    if header {
+        let names = ["source", "target"].iter().chain(columns.iter());
+        push_row(&mut out, names.copied(), delimiter);
+    }
+    for (index, edge) in graph.raw_edges().iter().enumerate() {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|name| match &edge.weight {
+                Value::Object(object) => value_text(object.get(*name)),
+                weight if *name == "weight" => value_text(Some(weight)),
+                _ => String::new(),
+            })
+            .collect();
+        let endpoints = [
+            keys[edge.source().index()].as_str(),
+            keys[edge.target().index()].as_str(),
+        ];
+        let row = endpoints
+            .iter()
+            .copied()
+            .chain(cells.iter().map(String::as_str));
+        push_row(&mut out, row, delimiter);
+        if (index + 1) % CHUNK_ROWS == 0 {
+            write(&out)?;
+            out.clear();
+        }
+    }
+    if !out.is_empty() {
+        write(&out)?;
+    }
+    Ok(())
+}
+
+/// Write an adjacency list in chunks of rows, with a row for every node
+/// listing its successors in edge order.
+pub fn write_adjacency_list<E>(
+    graph: &JsonGraph,
+    keys: &[String],
+    delimiter: char,
+    mut write: impl FnMut(&str) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut successors = vec![Vec::new(); graph.node_count()];
+    for edge in graph.raw_edges() {
+        successors[edge.source().index()].push(keys[edge.target().index()].as_str());
+    }
+    let mut out = String::new();
+    for (index, (key, successors)) in keys.iter().zip(&successors).enumerate() {
+        let row = std::iter::once(key.as_str()).chain(successors.iter().copied());
+        push_row(&mut out, row, delimiter);
+        if (index + 1) % CHUNK_ROWS == 0 {
+            write(&out)?;
+            out.clear();
+        }
+    }
+    if !out.is_empty() {
+        write(&out)?;
+    }
+    Ok(())
+}
+
+fn get_delimiter_option(options: &JsValue) -> Result<char, JsValue> {
+    let delimiter = match get_string_option(options, "delimiter")? {
+        None => return Ok(','),
+        Some(delimiter) => delimiter,
+    };
+    let mut chars = delimiter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '"' && c != '\n' && c != '\r' => Ok(c),
+        _ => Err(
+            GraphError::from_message("Option `delimiter` must be a single character").to_js_value(),
+        ),
+    }
+}
+
+impl EdgeListOptions {
+    /// Read edge list options from a JS options object such as
+    /// `{ delimiter: "\t", sourceColumn: "from", weightColumns: ["cost"] }`.
+    pub fn from_options(options: &JsValue) -> Result<Self, JsValue> {
+        let default = EdgeListOptions::default();
+        Ok(EdgeListOptions {
+            delimiter: get_delimiter_option(options)?,
+            header: get_bool_option(options, "header", default.header)?,
+            columns: get_string_array_option(options, "columns")?,
+            source_column: get_string_option(options, "sourceColumn")?,
+            target_column: get_string_option(options, "targetColumn")?,
+            weight_columns: get_string_array_option(options, "weightColumns")?,
+            parse_numbers: get_bool_option(options, "parseNumbers", default.parse_numbers)?,
+        })
+    }
+}
+
+/// Write text through an `onChunk` callback if given, otherwise collect and
+/// return it.
+fn write_text(
+    options: &JsValue,
+    write: impl FnOnce(&mut dyn FnMut(&str) -> Result<(), JsValue>) -> Result<(), JsValue>,
+) -> Result<Option<String>, JsValue> {
+    match get_function_option(options, "onChunk")? {
+        Some(on_chunk) => {
+            write(&mut |chunk| {
+                on_chunk
+                    .call1(&JsValue::NULL, &JsValue::from_str(chunk))
+                    .map(|_| ())
+            })?;
+            Ok(None)
+        }
+        None => {
+            let mut text = String::new();
+            write(&mut |chunk| {
+                text.push_str(chunk);
+                Ok(())
+            })?;
+            Ok(Some(text))
+        }
+    }
+}
+
+/// Streaming edge list reader, which is given the text in chunks such as the
+/// strings of a decoded read stream:
+///
+/// ```js
+/// const reader = new EdgeListReader({ delimiter: "\t" })
+/// for await (const chunk of stream) {
+///     reader.push(chunk)
+/// }
+/// const graph = reader.finish()
+/// ```
+#[wasm_bindgen]
+pub struct EdgeListReader {
+    parser: EdgeListParser<JsValue>,
+}
+
+#[wasm_bindgen]
+impl EdgeListReader {
+    /// Create a reader, taking the options of `DiGraph.fromEdgeList`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<EdgeListReader, JsValue> {
+        Ok(EdgeListReader {
+            parser: EdgeListParser::new(EdgeListOptions::from_options(&options)?, json_to_js),
+        })
+    }
+
+    /// Parse the next chunk of text. Records may span chunks.
+    pub fn push(&mut self, chunk: &str) -> Result<(), JsValue> {
+        self.parser.push(chunk).map_err(|err| err.to_js_value())
+    }
+
+    /// Parse the rest of the text and get the graph.
+    pub fn finish(self) -> Result<DiGraph, JsValue> {
+        self.parser
+            .finish()
+            .map(DiGraph::from)
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+/// Streaming adjacency list reader, used like `EdgeListReader`.
+#[wasm_bindgen]
+pub struct AdjacencyListReader {
+    parser: AdjacencyListParser<JsValue>,
+}
+
+#[wasm_bindgen]
+impl AdjacencyListReader {
+    /// Create a reader, taking the options of `DiGraph.fromAdjacencyList`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<AdjacencyListReader, JsValue> {
+        Ok(AdjacencyListReader {
+            parser: AdjacencyListParser::new(get_delimiter_option(&options)?, json_to_js),
+        })
+    }
+
+    /// Parse the next chunk of text. Records may span chunks.
+    pub fn push(&mut self, chunk: &str) -> Result<(), JsValue> {
+        self.parser.push(chunk).map_err(|err| err.to_js_value())
+    }
+
+    /// Parse the rest of the text and get the graph.
+    pub fn finish(self) -> Result<DiGraph, JsValue> {
+        self.parser
+            .finish()
+            .map(DiGraph::from)
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Create a graph from a delimited edge list such as
+    /// `source,target,weight`. Nodes are created for every distinct key in
+    /// the source and target columns, in order of first appearance, with the
+    /// key as weight. Edge weights are objects of the non-empty weight
+    /// columns. Options:
+    ///
+    /// * `delimiter` - field delimiter, defaults to `","`. Use `"\t"` for TSV.
+    /// * `header` - whether the first row holds column names, defaults to
+    ///   `true`.
+    /// * `columns` - column names when there is no header, defaults to the
+    ///   column numbers `"0"`, `"1"`, ....
+    /// * `sourceColumn`, `targetColumn` - defaults to the `source` and
+    ///   `target` columns, or else the first two.
+    /// * `weightColumns` - columns stored in edge weights, defaults to all
+    ///   others.
+    /// * `parseNumbers` - store numeric cells of weight columns as numbers,
+    ///   defaults to `false`. Node keys are never parsed.
+    ///
+    /// Errors have a `location` with the row and the column number. Use
+    /// `EdgeListReader` to read large files in chunks.
+    #[wasm_bindgen(js_name = fromEdgeList)]
+    pub fn from_edge_list(text: &str, options: JsValue) -> Result<DiGraph, JsValue> {
+        let options = EdgeListOptions::from_options(&options)?;
+        let mut parser = EdgeListParser::new(options, json_to_js);
+        parser
+            .push(text)
+            .and_then(|_| parser.finish())
+            .map(DiGraph::from)
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Write the graph as a delimited edge list with a column for every
+    /// property of object edge weights, other weights go in a `weight`
+    /// column. Nodes are written as their weight if it's a string or number
+    /// and as their index otherwise. Options:
+    ///
+    /// * `delimiter` - field delimiter, defaults to `","`.
+    /// * `header` - write a header row, defaults to `true`.
+    /// * `keyProperty` - node weight property used as the node key.
+    /// * `onChunk` - function called with the text in chunks of rows, in
+    ///   which case nothing is returned.
+    #[wasm_bindgen(js_name = toEdgeList)]
+    pub fn to_edge_list(&self, options: JsValue) -> Result<Option<String>, JsValue> {
+        let delimiter = get_delimiter_option(&options)?;
+        let header = get_bool_option(&options, "header", true)?;
+        let key_property = get_string_option(&options, "keyProperty")?;
+        let graph = self.to_json_graph().map_err(|err| err.to_js_value())?;
+        let keys = node_keys(&graph, key_property.as_deref()).map_err(|err| err.to_js_value())?;
+        write_text(&options, |write| {
+            write_edge_list(&graph, &keys, delimiter, header, write)
+        })
+    }
+
+    /// Create a graph from a delimited adjacency list, where every row is a
+    /// node key followed by the keys of its successors. Node weights are the
+    /// keys and edge weights are `null`. Options:
+    ///
+    /// * `delimiter` - field delimiter, defaults to `","`. Empty fields are
+    ///   skipped, so `" "` also reads lists separated by multiple spaces.
+    ///
+    /// Use `AdjacencyListReader` to read large files in chunks.
+    #[wasm_bindgen(js_name = fromAdjacencyList)]
+    pub fn from_adjacency_list(text: &str, options: JsValue) -> Result<DiGraph, JsValue> {
+        let mut parser = AdjacencyListParser::new(get_delimiter_option(&options)?, json_to_js);
+        parser
+            .push(text)
+            .and_then(|_| parser.finish())
+            .map(DiGraph::from)
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Write the graph as a delimited adjacency list with a row for every
+    /// node. Takes the `delimiter`, `keyProperty` and `onChunk` options of
+    /// `toEdgeList`.
+    #[wasm_bindgen(js_name = toAdjacencyList)]
+    pub fn to_adjacency_list(&self, options: JsValue) -> Result<Option<String>, JsValue> {
+        let delimiter = get_delimiter_option(&options)?;
+        let key_property = get_string_option(&options, "keyProperty")?;
+        let graph = self.to_json_graph().map_err(|err| err.to_js_value())?;
+        let keys = node_keys(&graph, key_property.as_deref()).map_err(|err| err.to_js_value())?;
+        write_text(&options, |write| {
+            write_adjacency_list(&graph, &keys, delimiter, write)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use serde_json::json;
+    use std::convert::identity;
+    use wasm_bindgen_test::*;
+
+    fn read_records(chunks: &[&str], delimiter: char) -> Vec<Record> {
+        let mut reader = RecordReader::new(delimiter);
+        let mut records = Vec::new();
+        for chunk in chunks {
+            reader.push(chunk, &mut records);
+        }
+        reader.finish(&mut records).unwrap();
+        records
+    }
+
+    fn record(row: u32, fields: &[&str]) -> Record {
+        Record {
+            row,
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    fn parse_edge_list(text: &str, options: EdgeListOptions) -> Result<JsonGraph, GraphError> {
+        let mut parser = EdgeListParser::new(options, identity);
+        parser.push(text)?;
+        parser.finish()
+    }
+
+    fn endpoints(graph: &JsonGraph) -> Vec<(usize, usize)> {
+        graph
+            .raw_edges()
+            .iter()
+            .map(|e| (e.source().index(), e.target().index()))
+            .collect()
+    }
+
+    #[test]
+    fn can_read_records_in_chunks() {
+        let records = read_records(
+            &[
+                "a,\"b \"",
+                "\"c\"\"\",d\r\n\n",
+                "\"multi\nline\",x\ne",
+                "\tf",
+            ],
+            ',',
+        );
+        assert_eq!(
+            records,
+            vec![
+                record(1, &["a", "b \"c\"", "d"]),
+                record(3, &["multi\nline", "x"]),
+                record(5, &["e\tf"]),
+            ]
+        );
+        assert_eq!(
+            read_records(&["a\tb\t\n"], '\t'),
+            vec![record(1, &["a", "b", ""])]
+        );
+        let mut reader = RecordReader::new(',');
+        reader.push("a\n\"b,c", &mut Vec::new());
+        assert_eq!(
+            reader.finish(&mut Vec::new()).unwrap_err(),
+            GraphError::from_message("Unterminated quoted field").with_location(2, 1)
+        );
+    }
+
+    #[test]
+    fn can_parse_edge_list() {
+        let text = "target,source,cost,label\nb,a,1.5,x\nc,a,2,\na,c,,\"y,z\"\n007,7,,\n";
+        let options = EdgeListOptions {
+            parse_numbers: true,
+            ..EdgeListOptions::default()
+        };
+        let mut parser = EdgeListParser::new(options, identity);
+        for chunk in text.as_bytes().chunks(5) {
+            parser.push(std::str::from_utf8(chunk).unwrap()).unwrap();
+        }
+        let graph = parser.finish().unwrap();
+        let weights: Vec<&Value> = graph.raw_nodes().iter().map(|n| &n.weight).collect();
+        assert_eq!(weights, vec!["a", "b", "c", "7", "007"]);
+        assert_eq!(endpoints(&graph), vec![(0, 1), (0, 2), (2, 0), (3, 4)]);
+        assert_eq!(
+            graph.raw_edges()[0].weight,
+            json!({ "cost": 1.5, "label": "x" })
+        );
+        assert_eq!(graph.raw_edges()[1].weight, json!({ "cost": 2 }));
+        assert_eq!(graph.raw_edges()[2].weight, json!({ "label": "y,z" }));
+    }
+
+    #[test]
+    fn can_parse_edge_list_with_options() {
+        let options = EdgeListOptions {
+            delimiter: '\t',
+            header: false,
+            columns: Some(vec![
+                String::from("from"),
+                String::from("to"),
+                String::from("id"),
+                String::from("note"),
+            ]),
+            source_column: Some(String::from("from")),
+            target_column: Some(String::from("to")),
+            weight_columns: Some(vec![String::from("id")]),
+            parse_numbers: false,
+        };
+        let graph = parse_edge_list("1\t2\t007\tignored\n2\t1\t8\n", options).unwrap();
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "id": "007" }));
+        assert_eq!(endpoints(&graph), vec![(0, 1), (1, 0)]);
+
+        let options = EdgeListOptions {
+            header: false,
+            ..EdgeListOptions::default()
+        };
+        let graph = parse_edge_list("a,b,3\n", options).unwrap();
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "2": "3" }));
+    }
+
+    #[test]
+    fn can_report_edge_list_rows() {
+        let error =
+            |text: &str, options: EdgeListOptions| parse_edge_list(text, options).unwrap_err();
+        assert_eq!(
+            error("source,target\na,b\n\nc,\n", EdgeListOptions::default()),
+            GraphError::from_message("Invalid edge list: empty target").with_location(4, 2)
+        );
+        assert_eq!(
+            error(
+                "source,target\n",
+                EdgeListOptions {
+                    weight_columns: Some(vec![String::from("cost")]),
+                    ..EdgeListOptions::default()
+                }
+            ),
+            GraphError::from_message("Invalid edge list: unknown column `cost`")
+                .with_location(1, 1)
+        );
+        assert_eq!(
+            error("only\n", EdgeListOptions::default()),
+            GraphError::from_message("Invalid edge list: expected at least 2 columns")
+                .with_location(1, 1)
+        );
+    }
+
+    #[test]
+    fn can_parse_adjacency_list() {
+        let mut parser = AdjacencyListParser::new(' ', identity);
+        parser.push("a b  c\nb\nd a\n").unwrap();
+        let graph = parser.finish().unwrap();
+        let weights: Vec<&Value> = graph.raw_nodes().iter().map(|n| &n.weight).collect();
+        assert_eq!(weights, vec!["a", "b", "c", "d"]);
+        assert_eq!(endpoints(&graph), vec![(0, 1), (0, 2), (3, 0)]);
+    }
+
+    #[test]
+    fn can_write_edge_and_adjacency_lists() {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!("a,1"));
+        let b = graph.add_node(json!({ "name": "b" }));
+        graph.add_edge(a, b, json!({ "cost": 2, "note": "say \"hi\"" }));
+        graph.add_edge(b, a, json!(true));
+        graph.add_edge(a, a, Value::Null);
+        let keys = node_keys(&graph, None).unwrap();
+        assert_eq!(keys, vec!["a,1", "1"]);
+
+        let mut text = String::new();
+        write_edge_list(&graph, &keys, ',', true, |chunk| {
+            text.push_str(chunk);
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+        assert_eq!(
+            text,
+            "source,target,cost,note,weight\n\
+             \"a,1\",1,2,\"say \"\"hi\"\"\",\n\
+             1,\"a,1\",,,true\n\
+             \"a,1\",\"a,1\",,,\n"
+        );
+        let options = EdgeListOptions {
+            parse_numbers: true,
+            ..EdgeListOptions::default()
+        };
+        let reparsed = parse_edge_list(&text, options).unwrap();
+        assert_eq!(endpoints(&reparsed), endpoints(&graph));
+        assert_eq!(reparsed.raw_edges()[0].weight, graph.raw_edges()[0].weight);
+
+        let mut text = String::new();
+        write_adjacency_list(&graph, &keys, '\t', |chunk| {
+            text.push_str(chunk);
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+        assert_eq!(text, "a,1\t1\ta,1\n1\ta,1\n");
+    }
+
+    #[test]
+    fn can_write_rows_in_chunks() {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!("a"));
+        for _ in 0..CHUNK_ROWS + 1 {
+            graph.add_edge(a, a, Value::Null);
+        }
+        let keys = node_keys(&graph, None).unwrap();
+        let mut chunks = Vec::new();
+        write_edge_list(&graph, &keys, ',', false, |chunk| {
+            chunks.push(chunk.len());
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+        assert_eq!(chunks, vec![4 * CHUNK_ROWS, 4]);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_edge_list() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let text = g.to_edge_list(JsValue::UNDEFINED).unwrap().unwrap();
+        assert!(text.starts_with("source,target\nVilnius,NYC\n"));
+        let imported = DiGraph::from_edge_list(&text, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.edge_count(), 9);
+        let text = g.to_adjacency_list(JsValue::UNDEFINED).unwrap().unwrap();
+        let imported = DiGraph::from_adjacency_list(&text, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.node_weights(), g.node_weights());
+        assert_eq!(imported.edge_count(), 9);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_read_lists_in_chunks() {
+        let mut reader = EdgeListReader::new(JsValue::UNDEFINED).unwrap();
+        reader.push("source,target,cost\n007,").unwrap();
+        reader.push("7,2\n").unwrap();
+        let g = reader.finish().unwrap();
+        assert_eq!(g.node_weights(), vec!["007", "7"]);
+        let mut reader = AdjacencyListReader::new(JsValue::UNDEFINED).unwrap();
+        reader.push("a,b\nb").unwrap();
+        reader.push(",c\n").unwrap();
+        let g = reader.finish().unwrap();
+        assert_eq!(g.edge_count(), 2);
+    }
+}
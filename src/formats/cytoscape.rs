@@ -0,0 +1,380 @@
+//! Conversion to and from Cytoscape.js elements JSON, as used by `cy.add` and
+//! `cy.json()`.
+
+use crate::formats::json::JsonGraph;
+use crate::formats::{id_text, node_ids};
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::get_string_option;
+use crate::{GraphError, GraphItemType};
+use petgraph::graph::NodeIndex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Weight properties which are fields of the element instead of its `data`.
+const ELEMENT_FIELDS: [&str; 2] = ["position", "classes"];
+
+fn invalid_elements(message: &str) -> GraphError {
+    GraphError::from_message(&format!("Invalid Cytoscape elements: {}", message))
+}
+
+/// Create an element from a weight and the `id`, `source` and `target` data
+/// fields. Object weights with a property of the same name as one of the
+/// fields, other than `skip`, are rejected as it would be lost.
+fn to_element(
+    weight: &Value,
+    fields: Vec<(&str, Value)>,
+    skip: Option<&str>,
+    item_type: GraphItemType,
+    index: usize,
+) -> Result<Value, GraphError> {
+    let mut element = Map::new();
+    let mut data = Map::new();
+    match weight {
+        Value::Null => {}
+        Value::Object(object) => {
+            for (key, value) in object {
+                if ELEMENT_FIELDS.contains(&key.as_str()) {
+                    element.insert(key.clone(), value.clone());
+                } else if Some(key.as_str()) != skip {
+                    if fields.iter().any(|(field, _)| field == key) {
+                        return Err(GraphError::new(
+                            &format!("Weight property `{}` is reserved by Cytoscape", key),
+                            item_type,
+                            index as u32,
+                        ));
+                    }
+                    data.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        _ => {
+            data.insert(String::from("weight"), weight.clone());
+        }
+    }
+    for (key, value) in fields {
+        data.insert(String::from(key), value);
+    }
+    element.insert(String::from("data"), Value::Object(data));
+    Ok(Value::Object(element))
+}
+
+/// Convert a graph to `{ nodes, edges }` elements. Nodes get their ids from
+/// `node_ids` and edges get the ids `e0`, `e1`, ... by index.
+pub fn to_cytoscape(graph: &JsonGraph, id_property: Option<&str>) -> Result<Value, GraphError> {
+    let ids = node_ids(graph, id_property)?;
+    let nodes = graph
+        .raw_nodes()
+        .iter()
+        .zip(&ids)
+        .enumerate()
+        .map(|(index, (node, id))| {
+            to_element(
+                &node.weight,
+                vec![("id", Value::from(id.as_str()))],
+                id_property,
+                GraphItemType::Node,
+                index,
+            )
+        })
+        .collect::<Result<_, _>>()?;
+    let edges = graph
+        .raw_edges()
+        .iter()
+        .enumerate()
+        .map(|(index, edge)| {
+            let fields = vec![
+                ("id", Value::from(format!("e{}", index))),
+                ("source", Value::from(ids[edge.source().index()].as_str())),
+                ("target", Value::from(ids[edge.target().index()].as_str())),
+            ];
+            to_element(&edge.weight, fields, None, GraphItemType::Edge, index)
+        })
+        .collect::<Result<_, _>>()?;
+    let mut elements = Map::new();
+    elements.insert(String::from("nodes"), Value::Array(nodes));
+    elements.insert(String::from("edges"), Value::Array(edges));
+    Ok(Value::Object(elements))
+}
+
+/// Create a weight from an element's data without the given fields, and its
+/// `position` and `classes`.
+fn element_weight(
+    element: &Map<String, Value>,
+    data: &Map<String, Value>,
+    skip: &[&str],
+) -> Map<String, Value> {
+    let mut weight: Map<String, Value> = data
+        .iter()
+        .filter(|(key, _)| !skip.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    for field in &ELEMENT_FIELDS {
+        if let Some(value) = element.get(*field) {
+            weight.insert(String::from(*field), value.clone());
+        }
+    }
+    weight
+}
+
+/// Convert Cytoscape elements to a graph, given either as `{ nodes, edges }`
+/// or as an array of elements, which are edges if their `group` is `"edges"`
+/// or they have no `group` but a `data.source`. Nodes are added in order.
+pub fn from_cytoscape(
+    elements: &Value,
+    id_property: Option<&str>,
+) -> Result<JsonGraph, GraphError> {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    match elements {
+        Value::Array(elements) => {
+            for element in elements {
+                let is_edge = match element.get("group") {
+                    Some(group) => group == "edges",
+                    None => element.pointer("/data/source").is_some(),
+                };
+                if is_edge {
+                    edges.push(element);
+                } else {
+                    nodes.push(element);
+                }
+            }
+        }
+        Value::Object(object) => {
+            for (key, list) in [("nodes", &mut nodes), ("edges", &mut edges)].iter_mut() {
+                match object.get(*key) {
+                    None | Some(Value::Null) => {}
+                    Some(Value::Array(elements)) => list.extend(elements),
+                    Some(_) => {
+                        return Err(invalid_elements(&format!("`{}` must be an array", key)))
+                    }
+                }
+            }
+        }
+        _ => return Err(invalid_elements("expected an object or an array")),
+    }
+
+    let mut graph = JsonGraph::with_capacity(nodes.len(), edges.len());
+    let mut node_indices = HashMap::new();
+    for (index, node) in nodes.into_iter().enumerate() {
+        let element = node.as_object();
+        let data = element.and_then(|element| element.get("data")?.as_object());
+        let (element, data) = match (element, data) {
+            (Some(element), Some(data)) => (element, data),
+            _ => {
+                return Err(invalid_elements(&format!(
+                    "node {} is missing `data`",
+                    index
+                )))
+            }
+        };
+        let id = id_text(data.get("id"))
+            .ok_or_else(|| invalid_elements(&format!("node {} is missing a `data.id`", index)))?;
+        let mut weight = element_weight(element, data, &["id"]);
+        if let Some(id_property) = id_property {
+            weight.insert(String::from(id_property), data["id"].clone());
+        }
+        let node_index = graph.add_node(Value::Object(weight));
+        if node_indices.insert(id.clone(), node_index).is_some() {
+            return Err(invalid_elements(&format!("duplicate node id `{}`", id)));
+        }
+    }
+    for (index, edge) in edges.into_iter().enumerate() {
+        let element = edge.as_object();
+        let data = element.and_then(|element| element.get("data")?.as_object());
+        let (element, data) = match (element, data) {
+            (Some(element), Some(data)) => (element, data),
+            _ => {
+                return Err(invalid_elements(&format!(
+                    "edge {} is missing `data`",
+                    index
+                )))
+            }
+        };
+        let mut endpoints = [NodeIndex::new(0); 2];
+        for (endpoint, field) in endpoints.iter_mut().zip(&["source", "target"]) {
+            let id = id_text(data.get(*field)).ok_or_else(|| {
+                invalid_elements(&format!("edge {} is missing a `data.{}`", index, field))
+            })?;
+            *endpoint = *node_indices.get(&id).ok_or_else(|| {
+                invalid_elements(&format!("edge {} references unknown node `{}`", index, id))
+            })?;
+        }
+        let weight = element_weight(element, data, &["id", "source", "target"]);
+        graph.add_edge(endpoints[0], endpoints[1], Value::Object(weight));
+    }
+    Ok(graph)
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Convert the graph to Cytoscape.js elements
+    /// `{ nodes: [{ data }], edges: [{ data: { source, target } }] }`.
+    ///
+    /// Nodes get the ids `n0`, `n1`, ... and edges `e0`, `e1`, ... by index.
+    /// Properties of object weights become `data` fields, except for
+    /// `position` and `classes` which become fields of the element, and
+    /// `id`, `source` and `target` which throw a `GraphError` as they are
+    /// reserved for the element's own fields. Other weights are stored as
+    /// `data.weight`. Options:
+    ///
+    /// * `idProperty` - node weight property used as the node id instead of
+    ///   the index based one.
+    #[wasm_bindgen(js_name = toCytoscape)]
+    pub fn to_cytoscape(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let id_property = get_string_option(&options, "idProperty")?;
+        let elements = self
+            .to_json_graph()
+            .and_then(|graph| to_cytoscape(&graph, id_property.as_deref()))
+            .map_err(|err| err.to_js_value())?;
+        JsValue::from_serde(&elements).map_err(|_| JsValue::from_str("Failed to transform graph"))
+    }
+
+    /// Create a graph from Cytoscape.js elements, given either as
+    /// `{ nodes, edges }` or as an array of elements. Weights are the `data`
+    /// objects without `id`, `source` and `target`, with the element's
+    /// `position` and `classes` added. Options:
+    ///
+    /// * `idProperty` - node weight property to store the node id in.
+    #[wasm_bindgen(js_name = fromCytoscape)]
+    pub fn from_cytoscape(elements: JsValue, options: JsValue) -> Result<DiGraph, JsValue> {
+        let id_property = get_string_option(&options, "idProperty")?;
+        let elements: Value = elements
+            .into_serde()
+            .map_err(|err| invalid_elements(&err.to_string()).to_js_value())?;
+        from_cytoscape(&elements, id_property.as_deref())
+            .and_then(|graph| DiGraph::from_json_graph(&graph))
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use petgraph::graph::EdgeIndex;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    fn new_json_graph() -> JsonGraph {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({
+            "name": "a",
+            "position": { "x": 1, "y": 2 },
+            "classes": ["big"]
+        }));
+        let b = graph.add_node(json!("b"));
+        graph.add_edge(a, b, json!({ "weight": 3 }));
+        graph.add_edge(b, b, Value::Null);
+        graph
+    }
+
+    #[test]
+    fn can_convert_to_cytoscape() {
+        let elements = to_cytoscape(&new_json_graph(), None).unwrap();
+        assert_eq!(
+            elements,
+            json!({
+                "nodes": [
+                    {
+                        "data": { "id": "n0", "name": "a" },
+                        "position": { "x": 1, "y": 2 },
+                        "classes": ["big"]
+                    },
+                    { "data": { "id": "n1", "weight": "b" } }
+                ],
+                "edges": [
+                    { "data": { "id": "e0", "source": "n0", "target": "n1", "weight": 3 } },
+                    { "data": { "id": "e1", "source": "n1", "target": "n1" } }
+                ]
+            })
+        );
+        let mut graph = new_json_graph();
+        graph[NodeIndex::new(1)] = json!({ "name": "b" });
+        let elements = to_cytoscape(&graph, Some("name")).unwrap();
+        assert_eq!(elements["nodes"][0]["data"], json!({ "id": "a" }));
+        assert_eq!(elements["edges"][0]["data"]["target"], "b");
+        graph[NodeIndex::new(1)] = json!({ "name": "b", "id": 2 });
+        assert_eq!(
+            to_cytoscape(&graph, None).unwrap_err(),
+            GraphError::new(
+                "Weight property `id` is reserved by Cytoscape",
+                GraphItemType::Node,
+                1
+            )
+        );
+        graph[NodeIndex::new(0)]["id"] = json!(1);
+        assert!(to_cytoscape(&graph, Some("id")).is_ok());
+        graph[EdgeIndex::new(0)] = json!({ "source": "a" });
+        assert_eq!(
+            to_cytoscape(&graph, Some("id")).unwrap_err(),
+            GraphError::new(
+                "Weight property `source` is reserved by Cytoscape",
+                GraphItemType::Edge,
+                0
+            )
+        );
+    }
+
+    #[test]
+    fn can_convert_from_cytoscape() {
+        let elements = to_cytoscape(&new_json_graph(), None).unwrap();
+        let graph = from_cytoscape(&elements, None).unwrap();
+        assert_eq!(
+            graph.raw_nodes()[0].weight,
+            json!({ "name": "a", "position": { "x": 1, "y": 2 }, "classes": ["big"] })
+        );
+        assert_eq!(graph.raw_nodes()[1].weight, json!({ "weight": "b" }));
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "weight": 3 }));
+        assert_eq!(graph.raw_edges()[1].source(), NodeIndex::new(1));
+        assert_eq!(graph.raw_edges()[1].target(), NodeIndex::new(1));
+    }
+
+    #[test]
+    fn can_convert_from_cytoscape_element_array() {
+        let elements = json!([
+            { "group": "edges", "data": { "source": "x", "target": 7 } },
+            { "data": { "id": "x" }, "classes": "a b" },
+            { "group": "nodes", "data": { "id": 7 } },
+            { "data": { "source": 7, "target": "x" } }
+        ]);
+        let graph = from_cytoscape(&elements, Some("key")).unwrap();
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(
+            graph.raw_nodes()[0].weight,
+            json!({ "key": "x", "classes": "a b" })
+        );
+        assert_eq!(graph.raw_nodes()[1].weight, json!({ "key": 7 }));
+        assert_eq!(graph.raw_edges()[1].source(), NodeIndex::new(1));
+    }
+
+    #[test]
+    fn can_reject_invalid_cytoscape_elements() {
+        let error = |elements: Value| from_cytoscape(&elements, None).unwrap_err();
+        assert_eq!(
+            error(json!({ "nodes": [{ "data": {} }] })),
+            invalid_elements("node 0 is missing a `data.id`")
+        );
+        assert_eq!(
+            error(json!({ "nodes": [{ "data": { "id": "a" } }, { "data": { "id": "a" } }] })),
+            invalid_elements("duplicate node id `a`")
+        );
+        assert_eq!(
+            error(json!({ "edges": [{ "data": { "source": "a", "target": "b" } }] })),
+            invalid_elements("edge 0 references unknown node `a`")
+        );
+        assert_eq!(
+            error(json!({ "nodes": {} })),
+            invalid_elements("`nodes` must be an array")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_cytoscape() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let elements = g.to_cytoscape(JsValue::UNDEFINED).unwrap();
+        let imported = DiGraph::from_cytoscape(elements, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.node_count(), 5);
+        assert_eq!(imported.edge_endpoints_all(), g.edge_endpoints_all());
+    }
+}
@@ -0,0 +1,330 @@
+//! Graphviz DOT export of `DiGraph`, built on petgraph's `Dot` formatter, and
+//! import with the parser in `parser`.
+
+pub mod parser;
+
+use crate::formats::json::JsonGraph;
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::{get_bool_option, get_function_option, get_index_array_option};
+use crate::{GraphError, GraphItemType};
+use js_sys::{Array, Function, Object, JSON};
+use petgraph::dot::{Config, Dot};
+use petgraph::graph::{EdgeReference, NodeIndex};
+use petgraph::visit::{EdgeRef, NodeFiltered};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Text used to label a node or edge by its weight, strings are used as is and
+/// other values as JSON. `null` and `undefined` give an empty label.
+pub fn weight_label(weight: &JsValue) -> String {
+    if let Some(text) = weight.as_string() {
+        return text;
+    }
+    if weight.is_null() || weight.is_undefined() {
+        return String::new();
+    }
+    JSON::stringify(weight)
+        .ok()
+        .and_then(|json| json.as_string())
+        .unwrap_or_default()
+}
+
+/// Quote and escape a string as a DOT ID.
+pub fn quote_dot_id(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn attributes_object(attributes: &[(String, String)]) -> Value {
+    let object: Map<String, Value> = attributes
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+        .collect();
+    Value::Object(object)
+}
+
+/// Convert a parsed DOT graph to a graph with JSON weights. Node weights are
+/// `{ id, attributes, subgraphs }` and edge weights are `{ attributes }`.
+pub fn dot_to_json_graph(dot: &parser::DotGraph) -> JsonGraph {
+    let mut graph = JsonGraph::with_capacity(dot.nodes.len(), dot.edges.len());
+    for node in &dot.nodes {
+        graph.add_node(json!({
+            "id": node.id,
+            "attributes": attributes_object(&node.attributes),
+            "subgraphs": node.subgraphs,
+        }));
+    }
+    for edge in &dot.edges {
+        graph.add_edge(
+            NodeIndex::new(edge.source),
+            NodeIndex::new(edge.target),
+            json!({ "attributes": attributes_object(&edge.attributes) }),
+        );
+    }
+    graph
+}
+
+impl From<parser::DotParseError> for GraphError {
+    fn from(err: parser::DotParseError) -> Self {
+        GraphError::from_message(&format!("Invalid DOT: {}", err.message))
+            .with_location(err.line, err.column)
+    }
+}
+
+/// Call an attribute callback with `(weight, index)` and format the returned
+/// object as a DOT attribute list body such as `"color"="red", "shape"="box"`.
+fn format_attributes(
+    callback: &Function,
+    weight: &JsValue,
+    index: usize,
+) -> Result<String, JsValue> {
+    let attributes = callback.call2(&JsValue::NULL, weight, &JsValue::from(index as u32))?;
+    if attributes.is_null() || attributes.is_undefined() {
+        return Ok(String::new());
+    }
+    let attributes = attributes.dyn_into::<Object>().map_err(|_| {
+        GraphError::from_message("DOT attribute callbacks must return an object").to_js_value()
+    })?;
+    let mut formatted = String::new();
+    for entry in Object::entries(&attributes).iter() {
+        let entry = Array::from(&entry);
+        let key = entry.get(0).as_string().unwrap_or_default();
+        let value = weight_label(&entry.get(1));
+        // Writing to a String can't fail.
+        let _ = write!(
+            formatted,
+            "{}={}, ",
+            quote_dot_id(&key),
+            quote_dot_id(&value)
+        );
+    }
+    Ok(formatted)
+}
+
+/// Call an optional attribute callback for every weight.
+fn collect_attributes<'a>(
+    callback: Option<Function>,
+    weights: impl Iterator<Item = &'a JsValue>,
+) -> Result<Vec<String>, JsValue> {
+    weights
+        .enumerate()
+        .map(|(index, weight)| match &callback {
+            Some(callback) => format_attributes(callback, weight, index),
+            None => Ok(String::new()),
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Export the graph in Graphviz DOT format. Options:
+    ///
+    /// * `nodeLabels` - label nodes by their weight, defaults to `true`.
+    /// * `edgeLabels` - label edges by their weight, defaults to `true`.
+    /// * `nodeAttributes` - function called as `nodeAttributes(weight, index)`
+    ///   returning an object of extra DOT attributes for the node, such as
+    ///   `{ color: "red", shape: "box" }`. A `label` attribute replaces the
+    ///   weight label.
+    /// * `edgeAttributes` - the same as `nodeAttributes` for edges.
+    /// * `nodes` - array of node indices to export, exporting the view of the
+    ///   graph with only these nodes and the edges between them.
+    ///
+    /// The attribute functions make it possible to highlight algorithm
+    /// results, for example by coloring nodes by their strongly connected
+    /// component.
+    #[wasm_bindgen(js_name = toDot)]
+    pub fn to_dot(&self, options: JsValue) -> Result<String, JsValue> {
+        let mut config = Vec::new();
+        if !get_bool_option(&options, "nodeLabels", true)? {
+            config.push(Config::NodeNoLabel);
+        }
+        if !get_bool_option(&options, "edgeLabels", true)? {
+            config.push(Config::EdgeNoLabel);
+        }
+        let node_attributes = collect_attributes(
+            get_function_option(&options, "nodeAttributes")?,
+            self.graph.raw_nodes().iter().map(|node| &node.weight),
+        )?;
+        let edge_attributes = collect_attributes(
+            get_function_option(&options, "edgeAttributes")?,
+            self.graph.raw_edges().iter().map(|edge| &edge.weight),
+        )?;
+        let keep_nodes = match get_index_array_option(&options, "nodes")? {
+            None => vec![true; self.graph.node_count()],
+            Some(nodes) => {
+                let mut keep_nodes = vec![false; self.graph.node_count()];
+                for index in nodes {
+                    match keep_nodes.get_mut(index) {
+                        Some(keep) => *keep = true,
+                        None => {
+                            return Err(GraphError::new(
+                                "No node exists for given index",
+                                GraphItemType::Node,
+                                index as u32,
+                            )
+                            .to_js_value())
+                        }
+                    }
+                }
+                keep_nodes
+            }
+        };
+
+        let labels = self.graph.map(
+            |_, weight| weight_label(weight),
+            |_, weight| weight_label(weight),
+        );
+        let view = NodeFiltered::from_fn(&labels, |node| keep_nodes[node.index()]);
+        let get_edge_attributes =
+            |_, edge: EdgeReference<String>| edge_attributes[edge.id().index()].clone();
+        let get_node_attributes =
+            |_, (node, _): (NodeIndex, &String)| node_attributes[node.index()].clone();
+        let dot =
+            Dot::with_attr_getters(&view, &config, &get_edge_attributes, &get_node_attributes);
+        Ok(format!("{}", dot))
+    }
+
+    /// Create a graph from Graphviz DOT text. Nodes are added in order of
+    /// first appearance with weights `{ id, attributes, subgraphs }`, where
+    /// `attributes` is a plain object of the node's DOT attributes, including
+    /// defaults from `node [...]` statements, and `subgraphs` lists the names
+    /// of the subgraphs and clusters the node was declared in. Edge weights
+    /// are `{ attributes }`.
+    ///
+    /// Edges of an undirected `graph` keep the direction they were written
+    /// in, `strict` graphs merge repeated edges. Graph attributes are ignored.
+    /// Syntax errors are thrown with their `location` as `{ line, column }`,
+    /// as are subgraphs nested more than 256 deep.
+    #[wasm_bindgen(js_name = fromDot)]
+    pub fn from_dot(text: &str) -> Result<DiGraph, JsValue> {
+        let dot = parser::parse_dot(text).map_err(|err| GraphError::from(err).to_js_value())?;
+        DiGraph::from_json_graph(&dot_to_json_graph(&dot)).map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use js_sys::Reflect;
+    use wasm_bindgen_test::*;
+
+    #[test]
+    fn can_quote_dot_ids() {
+        assert_eq!(quote_dot_id("NYC"), "\"NYC\"");
+        assert_eq!(quote_dot_id("say \"hi\"\r\n"), "\"say \\\"hi\\\"\\n\"");
+        assert_eq!(quote_dot_id("C:\\"), "\"C:\\\\\"");
+    }
+
+    #[test]
+    fn can_convert_dot_to_json_graph() {
+        let dot =
+            parser::parse_dot("digraph { subgraph cluster_a { a [color=red] } a -> b }").unwrap();
+        let graph = dot_to_json_graph(&dot);
+        assert_eq!(
+            graph.raw_nodes()[0].weight,
+            json!({ "id": "a", "attributes": { "color": "red" }, "subgraphs": ["cluster_a"] })
+        );
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "attributes": {} }));
+    }
+
+    #[test]
+    fn can_convert_dot_parse_errors() {
+        let err: GraphError = parser::parse_dot("digraph {\n  a -> ;\n}")
+            .unwrap_err()
+            .into();
+        assert_eq!(
+            err,
+            GraphError::from_message(
+                "Invalid DOT: Expected a node, edge, attribute or subgraph statement, found `;`"
+            )
+            .with_location(2, 8)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_export_dot() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let dot = g.to_dot(JsValue::UNDEFINED).unwrap();
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("    0 [ label = \"NYC\" ]\n"));
+        assert!(dot.contains("    1 -> 0 [ label = \"\" ]\n"));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_export_dot_with_attributes() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let options = Object::new();
+        let set = |key: &str, value: JsValue| {
+            Reflect::set(&options, &JsValue::from_str(key), &value).unwrap();
+        };
+        set("edgeLabels", JsValue::FALSE);
+        set(
+            "nodeAttributes",
+            Function::new_with_args("w, i", "return i === 2 ? { color: 'red' } : null").into(),
+        );
+        set(
+            "nodes",
+            Array::of2(&JsValue::from(2), &JsValue::from(3)).into(),
+        );
+        let dot = g.to_dot(options.into()).unwrap();
+        assert_eq!(
+            dot,
+            "digraph {\n    2 [ label = \"Knoxville\" \"color\"=\"red\", ]\n    \
+             3 [ label = \"Taipei\" ]\n    3 -> 2 [ ]\n}\n"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_invalid_export_nodes() {
+        let (g, _nodes, _edges) = new_test_graph();
+        for nodes in &[
+            JsValue::from(-1),
+            Array::of1(&JsValue::from(0.5)).into(),
+            Array::of1(&JsValue::from_f64(f64::NAN)).into(),
+        ] {
+            let options = Object::new();
+            Reflect::set(&options, &JsValue::from_str("nodes"), nodes).unwrap();
+            let err: GraphError = g.to_dot(options.into()).unwrap_err().into_serde().unwrap();
+            assert_eq!(
+                err,
+                GraphError::from_message("Option `nodes` must be an array of indices")
+            );
+        }
+        let options = Object::new();
+        let nodes = Array::of1(&JsValue::from(99));
+        Reflect::set(&options, &JsValue::from_str("nodes"), &nodes).unwrap();
+        let err: GraphError = g.to_dot(options.into()).unwrap_err().into_serde().unwrap();
+        assert_eq!(
+            err,
+            GraphError::new("No node exists for given index", GraphItemType::Node, 99)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_import_dot() {
+        let g = DiGraph::from_dot("digraph { a -> b [weight=2]; b -> c }").unwrap();
+        assert_eq!(g.node_count(), 3);
+        assert_eq!(g.edge_endpoints(1), Some(vec![1, 2]));
+        let weight = g.edge_weight(0).unwrap();
+        let attributes = Reflect::get(&weight, &JsValue::from_str("attributes")).unwrap();
+        assert_eq!(
+            Reflect::get(&attributes, &JsValue::from_str("weight")).unwrap(),
+            "2"
+        );
+        assert!(DiGraph::from_dot("digraph { a -> }").is_err());
+    }
+}
@@ -0,0 +1,955 @@
+//! Parser for the Graphviz DOT language, following the grammar at
+//! <https://graphviz.org/doc/info/lang.html>. It supports node, edge and
+//! attribute statements, default attributes, subgraphs (including clusters),
+//! ports and comments. HTML strings are kept with their `<` `>` delimiters.
+
+use std::collections::HashMap;
+
+/// Attributes in order of first definition, later definitions of the same key
+/// replace the value.
+pub type Attributes = Vec<(String, String)>;
+
+/// A parsed DOT graph.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DotGraph {
+    pub strict: bool,
+    pub directed: bool,
+    pub name: Option<String>,
+    pub attributes: Attributes,
+    /// Nodes in order of first appearance.
+    pub nodes: Vec<DotNode>,
+    pub edges: Vec<DotEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotNode {
+    pub id: String,
+    pub attributes: Attributes,
+    /// Names of the subgraphs the node was declared in, outermost first.
+    pub subgraphs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotEdge {
+    /// Index into `DotGraph::nodes`.
+    pub source: usize,
+    /// Index into `DotGraph::nodes`.
+    pub target: usize,
+    pub attributes: Attributes,
+}
+
+/// Error with the 1-based line and column in the DOT text where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotParseError {
+    pub message: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+fn set_attribute(attributes: &mut Attributes, key: &str, value: &str) {
+    match attributes.iter_mut().find(|(k, _)| k == key) {
+        Some((_, v)) => *v = value.to_string(),
+        None => attributes.push((key.to_string(), value.to_string())),
+    }
+}
+
+fn merge_attributes(attributes: &mut Attributes, other: &[(String, String)]) {
+    for (key, value) in other {
+        set_attribute(attributes, key, value);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    /// An identifier, numeral, quoted string or HTML string. Only unquoted
+    /// identifiers can be keywords.
+    Id {
+        text: String,
+        quoted: bool,
+    },
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semicolon,
+    Comma,
+    Equal,
+    Colon,
+    EdgeOp {
+        directed: bool,
+    },
+    Eof,
+}
+
+impl TokenKind {
+    fn describe(&self) -> String {
+        match self {
+            TokenKind::Id { text, .. } => format!("`{}`", text),
+            TokenKind::LBrace => String::from("`{`"),
+            TokenKind::RBrace => String::from("`}`"),
+            TokenKind::LBracket => String::from("`[`"),
+            TokenKind::RBracket => String::from("`]`"),
+            TokenKind::Semicolon => String::from("`;`"),
+            TokenKind::Comma => String::from("`,`"),
+            TokenKind::Equal => String::from("`=`"),
+            TokenKind::Colon => String::from("`:`"),
+            TokenKind::EdgeOp { directed: true } => String::from("`->`"),
+            TokenKind::EdgeOp { directed: false } => String::from("`--`"),
+            TokenKind::Eof => String::from("end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    line: u32,
+    column: u32,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: u32,
+    column: u32,
+    at_line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Lexer {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+            at_line_start: true,
+        }
+    }
+
+    fn error(&self, message: &str, line: u32, column: u32) -> DotParseError {
+        DotParseError {
+            message: String::from(message),
+            line,
+            column,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+            self.at_line_start = true;
+        } else {
+            self.column += 1;
+            if !c.is_whitespace() {
+                self.at_line_start = false;
+            }
+        }
+        Some(c)
+    }
+
+    /// Look at the character after the next one.
+    fn peek_second(&self) -> Option<char> {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next()
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), DotParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                // Lines starting with `#` are C preprocessor output.
+                Some('#') if self.at_line_start => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                Some('/') if self.peek_second() == Some('/') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                Some('/') if self.peek_second() == Some('*') => {
+                    let (line, column) = (self.line, self.column);
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            None => return Err(self.error("Unterminated comment", line, column)),
+                            Some('*') if self.peek() == Some('/') => {
+                                self.bump();
+                                break;
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn quoted_string(&mut self, line: u32, column: u32) -> Result<String, DotParseError> {
+        let mut text = String::new();
+        // Opening quote
+        self.bump();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("Unterminated string", line, column)),
+                Some('"') => return Ok(text),
+                Some('\\') => match self.peek() {
+                    // Pair escaped backslashes first, so that `"C:\\"` ends at
+                    // the last quote.
+                    Some('\\') => {
+                        self.bump();
+                        text.push('\\');
+                    }
+                    Some('"') => {
+                        self.bump();
+                        text.push('"');
+                    }
+                    // Escaped newlines continue the string on the next line.
+                    Some('\n') => {
+                        self.bump();
+                    }
+                    Some('\r') if self.peek_second() == Some('\n') => {
+                        self.bump();
+                        self.bump();
+                    }
+                    _ => text.push('\\'),
+                },
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn html_string(&mut self, line: u32, column: u32) -> Result<String, DotParseError> {
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            match self.bump() {
+                None => return Err(self.error("Unterminated HTML string", line, column)),
+                Some(c) => {
+                    text.push(c);
+                    match c {
+                        '<' => depth += 1,
+                        '>' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Ok(text);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_id_start(c: char) -> bool {
+        c.is_alphabetic() || c == '_' || !c.is_ascii()
+    }
+
+    fn is_id_char(c: char) -> bool {
+        Self::is_id_start(c) || c.is_ascii_digit()
+    }
+
+    fn numeral(&mut self, line: u32, column: u32) -> Result<String, DotParseError> {
+        let mut text = String::new();
+        if self.peek() == Some('-') {
+            text.push('-');
+            self.bump();
+        }
+        let mut seen_dot = false;
+        let mut seen_digit = false;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                seen_digit = true;
+            } else if c == '.' && !seen_dot {
+                seen_dot = true;
+            } else {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+        if !seen_digit {
+            return Err(self.error("Invalid number", line, column));
+        }
+        if matches!(self.peek(), Some(c) if Self::is_id_start(c)) {
+            return Err(self.error("Identifiers can't start with a digit", line, column));
+        }
+        Ok(text)
+    }
+
+    fn next_token(&mut self) -> Result<Token, DotParseError> {
+        self.skip_whitespace_and_comments()?;
+        let (line, column) = (self.line, self.column);
+        let token = |kind| Token { kind, line, column };
+        let c = match self.peek() {
+            None => return Ok(token(TokenKind::Eof)),
+            Some(c) => c,
+        };
+        let punctuation = match c {
+            '{' => Some(TokenKind::LBrace),
+            '}' => Some(TokenKind::RBrace),
+            '[' => Some(TokenKind::LBracket),
+            ']' => Some(TokenKind::RBracket),
+            ';' => Some(TokenKind::Semicolon),
+            ',' => Some(TokenKind::Comma),
+            '=' => Some(TokenKind::Equal),
+            ':' => Some(TokenKind::Colon),
+            _ => None,
+        };
+        if let Some(kind) = punctuation {
+            self.bump();
+            return Ok(token(kind));
+        }
+        match c {
+            '-' if self.peek_second() == Some('>') => {
+                self.bump();
+                self.bump();
+                Ok(token(TokenKind::EdgeOp { directed: true }))
+            }
+            '-' if self.peek_second() == Some('-') => {
+                self.bump();
+                self.bump();
+                Ok(token(TokenKind::EdgeOp { directed: false }))
+            }
+            '"' => {
+                let mut text = self.quoted_string(line, column)?;
+                // Quoted strings can be concatenated with `+`.
+                loop {
+                    self.skip_whitespace_and_comments()?;
+                    if self.peek() != Some('+') {
+                        break;
+                    }
+                    self.bump();
+                    self.skip_whitespace_and_comments()?;
+                    if self.peek() != Some('"') {
+                        return Err(self.error(
+                            "Expected a quoted string after `+`",
+                            self.line,
+                            self.column,
+                        ));
+                    }
+                    let (next_line, next_column) = (self.line, self.column);
+                    text.push_str(&self.quoted_string(next_line, next_column)?);
+                }
+                Ok(token(TokenKind::Id { text, quoted: true }))
+            }
+            '<' => {
+                let text = self.html_string(line, column)?;
+                Ok(token(TokenKind::Id { text, quoted: true }))
+            }
+            c if c == '-' || c == '.' || c.is_ascii_digit() => {
+                let text = self.numeral(line, column)?;
+                Ok(token(TokenKind::Id {
+                    text,
+                    quoted: false,
+                }))
+            }
+            c if Self::is_id_start(c) => {
+                let mut text = String::new();
+                while let Some(c) = self.peek() {
+                    if !Self::is_id_char(c) {
+                        break;
+                    }
+                    text.push(c);
+                    self.bump();
+                }
+                Ok(token(TokenKind::Id {
+                    text,
+                    quoted: false,
+                }))
+            }
+            c => Err(self.error(&format!("Unexpected character `{}`", c), line, column)),
+        }
+    }
+}
+
+/// Deepest nesting of subgraphs, which are parsed recursively.
+const MAX_SUBGRAPH_DEPTH: usize = 256;
+
+/// Default attributes and subgraph membership for the statements in a graph
+/// or subgraph body.
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    node_defaults: Attributes,
+    edge_defaults: Attributes,
+    subgraphs: Vec<String>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    graph: DotGraph,
+    node_indices: HashMap<String, usize>,
+    edge_indices: HashMap<(usize, usize), usize>,
+    anonymous_subgraphs: usize,
+    depth: usize,
+}
+
+/// An edge endpoint, either a single node with an optional port or all nodes
+/// of a subgraph.
+enum Endpoint {
+    Node(usize, Option<String>),
+    Subgraph(Vec<usize>),
+}
+
+impl Endpoint {
+    fn nodes(&self) -> Vec<(usize, Option<String>)> {
+        match self {
+            Endpoint::Node(node, port) => vec![(*node, port.clone())],
+            Endpoint::Subgraph(nodes) => nodes.iter().map(|&node| (node, None)).collect(),
+        }
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn peek_kind(&self, offset: usize) -> &TokenKind {
+        let index = (self.position + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].kind
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if self.position < self.tokens.len() - 1 {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn error_at(&self, token: &Token, message: &str) -> DotParseError {
+        DotParseError {
+            message: String::from(message),
+            line: token.line,
+            column: token.column,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> DotParseError {
+        let token = self.peek();
+        self.error_at(
+            token,
+            &format!("Expected {}, found {}", expected, token.kind.describe()),
+        )
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, DotParseError> {
+        if *self.peek_kind(0) == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.unexpected(&kind.describe()))
+        }
+    }
+
+    fn is_keyword(kind: &TokenKind, keyword: &str) -> bool {
+        match kind {
+            TokenKind::Id {
+                text,
+                quoted: false,
+            } => text.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        Self::is_keyword(self.peek_kind(0), keyword)
+    }
+
+    fn id(&mut self, expected: &str) -> Result<String, DotParseError> {
+        match self.peek_kind(0).clone() {
+            TokenKind::Id { text, quoted } => {
+                let is_keyword = !quoted
+                    && ["strict", "graph", "digraph", "node", "edge", "subgraph"]
+                        .iter()
+                        .any(|keyword| text.eq_ignore_ascii_case(keyword));
+                if is_keyword {
+                    return Err(self.unexpected(expected));
+                }
+                self.advance();
+                Ok(text)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn skip_separator(&mut self) {
+        if matches!(self.peek_kind(0), TokenKind::Semicolon | TokenKind::Comma) {
+            self.advance();
+        }
+    }
+
+    fn parse_graph(&mut self) -> Result<(), DotParseError> {
+        if self.at_keyword("strict") {
+            self.advance();
+            self.graph.strict = true;
+        }
+        if self.at_keyword("digraph") {
+            self.graph.directed = true;
+        } else if !self.at_keyword("graph") {
+            return Err(self.unexpected("`graph` or `digraph`"));
+        }
+        self.advance();
+        if let TokenKind::Id { .. } = self.peek_kind(0) {
+            self.graph.name = Some(self.id("graph name")?);
+        }
+        self.expect(TokenKind::LBrace)?;
+        let mut scope = Scope::default();
+        self.parse_statements(&mut scope)?;
+        self.expect(TokenKind::RBrace)?;
+        self.expect(TokenKind::Eof)?;
+        Ok(())
+    }
+
+    /// Parse statements up to the closing `}`, returning the nodes used in
+    /// them.
+    fn parse_statements(&mut self, scope: &mut Scope) -> Result<Vec<usize>, DotParseError> {
+        let mut nodes = Vec::new();
+        while *self.peek_kind(0) != TokenKind::RBrace {
+            if *self.peek_kind(0) == TokenKind::Eof {
+                return Err(self.unexpected("`}`"));
+            }
+            self.parse_statement(scope, &mut nodes)?;
+            if *self.peek_kind(0) == TokenKind::Semicolon {
+                self.advance();
+            }
+        }
+        Ok(nodes)
+    }
+
+    fn parse_statement(
+        &mut self,
+        scope: &mut Scope,
+        nodes: &mut Vec<usize>,
+    ) -> Result<(), DotParseError> {
+        if self.at_keyword("graph") {
+            self.advance();
+            let attributes = self.parse_attribute_lists()?;
+            if scope.subgraphs.is_empty() {
+                merge_attributes(&mut self.graph.attributes, &attributes);
+            }
+            return Ok(());
+        }
+        if self.at_keyword("node") {
+            self.advance();
+            let attributes = self.parse_attribute_lists()?;
+            merge_attributes(&mut scope.node_defaults, &attributes);
+            return Ok(());
+        }
+        if self.at_keyword("edge") {
+            self.advance();
+            let attributes = self.parse_attribute_lists()?;
+            merge_attributes(&mut scope.edge_defaults, &attributes);
+            return Ok(());
+        }
+        // `ID = ID` sets a graph attribute.
+        if let (TokenKind::Id { .. }, TokenKind::Equal) = (self.peek_kind(0), self.peek_kind(1)) {
+            let key = self.id("attribute name")?;
+            self.advance();
+            let value = self.id("attribute value")?;
+            if scope.subgraphs.is_empty() {
+                set_attribute(&mut self.graph.attributes, &key, &value);
+            }
+            return Ok(());
+        }
+
+        let first = self.parse_endpoint(scope)?;
+        if let TokenKind::EdgeOp { .. } = self.peek_kind(0) {
+            let mut endpoints = vec![first];
+            while let TokenKind::EdgeOp { directed } = self.peek_kind(0).clone() {
+                if directed != self.graph.directed {
+                    let message = if self.graph.directed {
+                        "Expected `->` in a digraph"
+                    } else {
+                        "Expected `--` in an undirected graph"
+                    };
+                    return Err(self.error_at(self.peek(), message));
+                }
+                self.advance();
+                endpoints.push(self.parse_endpoint(scope)?);
+            }
+            let mut attributes = scope.edge_defaults.clone();
+            merge_attributes(&mut attributes, &self.parse_optional_attribute_lists()?);
+            for endpoint in &endpoints {
+                nodes.extend(endpoint.nodes().into_iter().map(|(node, _)| node));
+            }
+            for pair in endpoints.windows(2) {
+                for (source, source_port) in pair[0].nodes() {
+                    for (target, target_port) in pair[1].nodes() {
+                        let mut edge_attributes = attributes.clone();
+                        if let Some(port) = &source_port {
+                            set_attribute(&mut edge_attributes, "tailport", port);
+                        }
+                        if let Some(port) = &target_port {
+                            set_attribute(&mut edge_attributes, "headport", port);
+                        }
+                        self.add_edge(source, target, edge_attributes);
+                    }
+                }
+            }
+        } else {
+            match first {
+                Endpoint::Node(node, _) => {
+                    let attributes = self.parse_optional_attribute_lists()?;
+                    merge_attributes(&mut self.graph.nodes[node].attributes, &attributes);
+                    nodes.push(node);
+                }
+                Endpoint::Subgraph(subgraph_nodes) => nodes.extend(subgraph_nodes),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_endpoint(&mut self, scope: &mut Scope) -> Result<Endpoint, DotParseError> {
+        if self.at_keyword("subgraph") || *self.peek_kind(0) == TokenKind::LBrace {
+            return Ok(Endpoint::Subgraph(self.parse_subgraph(scope)?));
+        }
+        let id = self.id("a node, edge, attribute or subgraph statement")?;
+        let mut port = None;
+        if *self.peek_kind(0) == TokenKind::Colon {
+            self.advance();
+            let mut port_text = self.id("port")?;
+            if *self.peek_kind(0) == TokenKind::Colon {
+                self.advance();
+                port_text.push(':');
+                port_text.push_str(&self.id("compass point")?);
+            }
+            port = Some(port_text);
+        }
+        Ok(Endpoint::Node(self.node(&id, scope), port))
+    }
+
+    fn parse_subgraph(&mut self, scope: &Scope) -> Result<Vec<usize>, DotParseError> {
+        let mut name = None;
+        if self.at_keyword("subgraph") {
+            self.advance();
+            if let TokenKind::Id { .. } = self.peek_kind(0) {
+                name = Some(self.id("subgraph name")?);
+            }
+        }
+        let name = name.unwrap_or_else(|| {
+            self.anonymous_subgraphs += 1;
+            format!("%{}", self.anonymous_subgraphs)
+        });
+        if self.depth == MAX_SUBGRAPH_DEPTH {
+            let message = format!("Subgraphs can't be nested over {} deep", MAX_SUBGRAPH_DEPTH);
+            return Err(self.error_at(self.peek(), &message));
+        }
+        self.expect(TokenKind::LBrace)?;
+        let mut inner = scope.clone();
+        inner.subgraphs.push(name);
+        self.depth += 1;
+        let nodes = self.parse_statements(&mut inner)?;
+        self.depth -= 1;
+        self.expect(TokenKind::RBrace)?;
+        Ok(nodes)
+    }
+
+    fn parse_optional_attribute_lists(&mut self) -> Result<Attributes, DotParseError> {
+        if *self.peek_kind(0) == TokenKind::LBracket {
+            self.parse_attribute_lists()
+        } else {
+            Ok(Attributes::new())
+        }
+    }
+
+    fn parse_attribute_lists(&mut self) -> Result<Attributes, DotParseError> {
+        let mut attributes = Attributes::new();
+        self.expect(TokenKind::LBracket)?;
+        loop {
+            while *self.peek_kind(0) != TokenKind::RBracket {
+                let key = self.id("attribute name")?;
+                self.expect(TokenKind::Equal)?;
+                let value = self.id("attribute value")?;
+                set_attribute(&mut attributes, &key, &value);
+                self.skip_separator();
+            }
+            self.advance();
+            if *self.peek_kind(0) != TokenKind::LBracket {
+                return Ok(attributes);
+            }
+            self.advance();
+        }
+    }
+
+    /// Get the index of a node, creating it with the scope's default
+    /// attributes on first use.
+    fn node(&mut self, id: &str, scope: &Scope) -> usize {
+        let index = match self.node_indices.get(id) {
+            Some(&index) => index,
+            None => {
+                let index = self.graph.nodes.len();
+                self.graph.nodes.push(DotNode {
+                    id: String::from(id),
+                    attributes: scope.node_defaults.clone(),
+                    subgraphs: Vec::new(),
+                });
+                self.node_indices.insert(String::from(id), index);
+                index
+            }
+        };
+        let subgraphs = &mut self.graph.nodes[index].subgraphs;
+        for subgraph in &scope.subgraphs {
+            if !subgraphs.contains(subgraph) {
+                subgraphs.push(subgraph.clone());
+            }
+        }
+        index
+    }
+
+    fn add_edge(&mut self, source: usize, target: usize, attributes: Attributes) {
+        if self.graph.strict {
+            // Strict graphs merge repeated edges, including self loops.
+            let key = if self.graph.directed || source <= target {
+                (source, target)
+            } else {
+                (target, source)
+            };
+            if let Some(&existing) = self.edge_indices.get(&key) {
+                merge_attributes(&mut self.graph.edges[existing].attributes, &attributes);
+                return;
+            }
+            self.edge_indices.insert(key, self.graph.edges.len());
+        }
+        self.graph.edges.push(DotEdge {
+            source,
+            target,
+            attributes,
+        });
+    }
+}
+
+/// Parse the text of a DOT graph.
+pub fn parse_dot(text: &str) -> Result<DotGraph, DotParseError> {
+    let mut lexer = Lexer::new(text);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let is_eof = token.kind == TokenKind::Eof;
+        tokens.push(token);
+        if is_eof {
+            break;
+        }
+    }
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        graph: DotGraph::default(),
+        node_indices: HashMap::new(),
+        edge_indices: HashMap::new(),
+        anonymous_subgraphs: 0,
+        depth: 0,
+    };
+    parser.parse_graph()?;
+    Ok(parser.graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes(pairs: &[(&str, &str)]) -> Attributes {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn error_location(text: &str) -> (u32, u32, String) {
+        let err = parse_dot(text).unwrap_err();
+        (err.line, err.column, err.message)
+    }
+
+    #[test]
+    fn can_parse_nodes_and_edges() {
+        let dot = parse_dot("digraph G { a; b [label=\"B\"]; a -> b -> c [color=red] }").unwrap();
+        assert!(dot.directed);
+        assert_eq!(dot.name, Some(String::from("G")));
+        let ids: Vec<&str> = dot.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(dot.nodes[1].attributes, attributes(&[("label", "B")]));
+        assert_eq!(dot.edges.len(), 2);
+        assert_eq!((dot.edges[0].source, dot.edges[0].target), (0, 1));
+        assert_eq!((dot.edges[1].source, dot.edges[1].target), (1, 2));
+        assert_eq!(dot.edges[1].attributes, attributes(&[("color", "red")]));
+    }
+
+    #[test]
+    fn can_parse_ids() {
+        let dot = parse_dot(
+            "graph { \"multi\" + \n \"part\" -- -1.5; _x2 -- <<b>html</b>>; \"q\\\"t\" -- \"a\\\nb\" }",
+        )
+        .unwrap();
+        let ids: Vec<&str> = dot.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["multipart", "-1.5", "_x2", "<<b>html</b>>", "q\"t", "ab"]
+        );
+        assert!(!dot.directed);
+    }
+
+    #[test]
+    fn can_parse_escaped_backslashes() {
+        let dot = parse_dot(r#"graph { "C:\\" -- "a\\\"b" -- "\\n" }"#).unwrap();
+        let ids: Vec<&str> = dot.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["C:\\", "a\\\"b", "\\n"]);
+    }
+
+    #[test]
+    fn can_apply_default_attributes_by_scope() {
+        let dot = parse_dot(
+            "digraph {
+                node [shape=box]
+                edge [color=blue]
+                a
+                subgraph cluster_0 {
+                    node [color=red]
+                    label = \"ignored\"
+                    b -> c
+                }
+                d -> b
+                a [shape=circle]
+            }",
+        )
+        .unwrap();
+        assert_eq!(dot.nodes[0].attributes, attributes(&[("shape", "circle")]));
+        assert_eq!(
+            dot.nodes[1].attributes,
+            attributes(&[("shape", "box"), ("color", "red")])
+        );
+        assert_eq!(dot.nodes[1].subgraphs, vec![String::from("cluster_0")]);
+        assert_eq!(dot.nodes[3].attributes, attributes(&[("shape", "box")]));
+        assert!(dot.nodes[3].subgraphs.is_empty());
+        assert_eq!(dot.edges[1].attributes, attributes(&[("color", "blue")]));
+        assert!(dot.attributes.is_empty());
+    }
+
+    #[test]
+    fn can_connect_subgraphs() {
+        let dot = parse_dot("digraph { a -> { b c } [w=1]; subgraph s { d } -> e }").unwrap();
+        let pairs: Vec<(usize, usize)> = dot.edges.iter().map(|e| (e.source, e.target)).collect();
+        assert_eq!(pairs, vec![(0, 1), (0, 2), (3, 4)]);
+        assert_eq!(dot.nodes[1].subgraphs, vec![String::from("%1")]);
+        assert_eq!(dot.nodes[3].subgraphs, vec![String::from("s")]);
+    }
+
+    #[test]
+    fn can_parse_ports_and_graph_attributes() {
+        let dot = parse_dot(
+            "digraph { rankdir=LR; graph [label=g]; a:p1:n -> b:s [x=1, y=2; z=3][w=4] }",
+        )
+        .unwrap();
+        assert_eq!(
+            dot.attributes,
+            attributes(&[("rankdir", "LR"), ("label", "g")])
+        );
+        assert_eq!(
+            dot.edges[0].attributes,
+            attributes(&[
+                ("x", "1"),
+                ("y", "2"),
+                ("z", "3"),
+                ("w", "4"),
+                ("tailport", "p1:n"),
+                ("headport", "s")
+            ])
+        );
+    }
+
+    #[test]
+    fn can_merge_strict_edges() {
+        let dot = parse_dot("strict graph { a -- b [x=1]; b -- a [y=2]; a -- a; a -- a }").unwrap();
+        assert!(dot.strict);
+        assert_eq!(dot.edges.len(), 2);
+        assert_eq!(
+            dot.edges[0].attributes,
+            attributes(&[("x", "1"), ("y", "2")])
+        );
+    }
+
+    #[test]
+    fn can_skip_comments() {
+        let dot = parse_dot(
+            "# preprocessor line\ndigraph { // line comment\n a /* block\n comment */ -> b }",
+        )
+        .unwrap();
+        assert_eq!(dot.edges.len(), 1);
+    }
+
+    #[test]
+    fn can_report_error_locations() {
+        assert_eq!(
+            error_location("digraph {\n  a -> ;\n}"),
+            (
+                2,
+                8,
+                String::from("Expected a node, edge, attribute or subgraph statement, found `;`")
+            )
+        );
+        assert_eq!(
+            error_location("digraph {\n  a -- b\n}"),
+            (2, 5, String::from("Expected `->` in a digraph"))
+        );
+        assert_eq!(
+            error_location("digraph { a [color red] }"),
+            (1, 20, String::from("Expected `=`, found `red`"))
+        );
+        assert_eq!(
+            error_location("digraph { a [label=\"open }"),
+            (1, 20, String::from("Unterminated string"))
+        );
+        assert_eq!(
+            error_location("digraph { a }\n}"),
+            (2, 1, String::from("Expected end of input, found `}`"))
+        );
+        assert_eq!(
+            error_location("tree { }"),
+            (
+                1,
+                1,
+                String::from("Expected `graph` or `digraph`, found `tree`")
+            )
+        );
+        assert_eq!(
+            error_location("digraph { a -> b"),
+            (1, 17, String::from("Expected `}`, found end of input"))
+        );
+        assert_eq!(
+            error_location("digraph { 1a }"),
+            (1, 11, String::from("Identifiers can't start with a digit"))
+        );
+    }
+
+    #[test]
+    fn can_limit_subgraph_nesting() {
+        let nested = |depth: usize| {
+            format!(
+                "digraph {{ {}a{} }}",
+                "{ ".repeat(depth),
+                " }".repeat(depth)
+            )
+        };
+        assert!(parse_dot(&nested(MAX_SUBGRAPH_DEPTH)).is_ok());
+        assert_eq!(
+            error_location(&nested(MAX_SUBGRAPH_DEPTH + 1)),
+            (
+                1,
+                11 + 2 * MAX_SUBGRAPH_DEPTH as u32,
+                String::from("Subgraphs can't be nested over 256 deep")
+            )
+        );
+        assert!(parse_dot(&nested(100_000)).is_err());
+    }
+}
@@ -0,0 +1,625 @@
+//! graph6, sparse6 and digraph6, the compact text encodings of nauty used by
+//! the standard graph catalogs. They store only the structure of a graph, so
+//! imported graphs have `null` weights. Files hold one graph per line.
+//!
+//! graph6 stores simple undirected graphs as the upper triangle of the
+//! adjacency matrix, sparse6 stores undirected multigraphs with self loops as
+//! a list of edges and digraph6 stores the full adjacency matrix of a directed
+//! graph with self loops.
+
+use crate::formats::json::JsonGraph;
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::get_bool_option;
+use crate::{GraphError, GraphItemType};
+use js_sys::Array;
+use petgraph::graph::{self, NodeIndex};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Graph6Format {
+    Graph6,
+    Sparse6,
+    Digraph6,
+}
+
+impl Graph6Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Graph6Format::Graph6 => "graph6",
+            Graph6Format::Sparse6 => "sparse6",
+            Graph6Format::Digraph6 => "digraph6",
+        }
+    }
+
+    /// Optional header at the start of a file.
+    fn header(self) -> &'static str {
+        match self {
+            Graph6Format::Graph6 => ">>graph6<<",
+            Graph6Format::Sparse6 => ">>sparse6<<",
+            Graph6Format::Digraph6 => ">>digraph6<<",
+        }
+    }
+
+    /// Character starting every encoded graph.
+    fn prefix(self) -> &'static str {
+        match self {
+            Graph6Format::Graph6 => "",
+            Graph6Format::Sparse6 => ":",
+            Graph6Format::Digraph6 => "&",
+        }
+    }
+}
+
+const FORMATS: [Graph6Format; 3] = [
+    Graph6Format::Graph6,
+    Graph6Format::Sparse6,
+    Graph6Format::Digraph6,
+];
+
+/// Packs bits into printable characters of 6 bits each.
+struct BitWriter {
+    out: String,
+    value: u8,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new(out: String) -> Self {
+        BitWriter {
+            out,
+            value: 0,
+            bits: 0,
+        }
+    }
+
+    fn push(&mut self, bit: bool) {
+        self.value = self.value << 1 | bit as u8;
+        self.bits += 1;
+        if self.bits == 6 {
+            self.out.push(char::from(self.value + 63));
+            self.value = 0;
+            self.bits = 0;
+        }
+    }
+
+    fn push_bits(&mut self, value: u64, count: u32) {
+        for shift in (0..count).rev() {
+            self.push(value >> shift & 1 == 1);
+        }
+    }
+
+    /// Number of bits needed to complete the last character.
+    fn padding(&self) -> u32 {
+        (6 - self.bits) % 6
+    }
+
+    fn finish(mut self, pad_with: bool) -> String {
+        while self.bits != 0 {
+            self.push(pad_with);
+        }
+        self.out
+    }
+}
+
+/// Largest node count read from sparse6, whose nodes aren't bounded by the
+/// length of the data as a few characters can give millions of isolated
+/// nodes.
+const MAX_SPARSE6_NODES: u64 = 1 << 20;
+
+/// Reads the bits of validated characters of 6 bits each.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: u64,
+}
+
+impl<'a> BitReader<'a> {
+    fn remaining(&self) -> u64 {
+        self.data.len() as u64 * 6 - self.position
+    }
+
+    fn bit(&mut self) -> bool {
+        let value = self.data[(self.position / 6) as usize] - 63;
+        let bit = value >> (5 - self.position % 6) & 1;
+        self.position += 1;
+        bit == 1
+    }
+
+    fn bits(&mut self, count: u32) -> u64 {
+        (0..count).fold(0, |value, _| value << 1 | self.bit() as u64)
+    }
+}
+
+fn push_node_count(out: &mut String, n: u64) {
+    let (prefix, groups) = match n {
+        0..=62 => ("", 1),
+        63..=258_047 => ("~", 3),
+        _ => ("~~", 6),
+    };
+    out.push_str(prefix);
+    for group in (0..groups).rev() {
+        out.push(char::from((n >> (6 * group) & 63) as u8 + 63));
+    }
+}
+
+/// Read the node count at the start of `data`, returning it with the number
+/// of characters it takes.
+fn read_node_count(data: &[u8]) -> Option<(u64, usize)> {
+    let value = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(0u64, |value, byte| value << 6 | u64::from(byte - 63))
+    };
+    match data {
+        [126, 126, rest @ ..] => rest.get(..6).map(|bytes| (value(bytes), 8)),
+        [126, rest @ ..] => rest.get(..3).map(|bytes| (value(bytes), 4)),
+        [byte, ..] => Some((u64::from(byte - 63), 1)),
+        [] => None,
+    }
+}
+
+/// Number of bits sparse6 uses for a node index.
+fn index_bits(n: u64) -> u32 {
+    if n <= 1 {
+        0
+    } else {
+        64 - (n - 1).leading_zeros()
+    }
+}
+
+fn edge_pairs<N, E>(graph: &graph::DiGraph<N, E>) -> impl Iterator<Item = (u64, u64)> + '_ {
+    graph
+        .raw_edges()
+        .iter()
+        .map(|edge| (edge.source().index() as u64, edge.target().index() as u64))
+}
+
+fn start_text(format: Graph6Format, header: bool, node_count: usize) -> String {
+    let mut out = String::new();
+    if header {
+        out.push_str(format.header());
+    }
+    out.push_str(format.prefix());
+    push_node_count(&mut out, node_count as u64);
+    out
+}
+
+/// Encode a graph as graph6, which stores each pair of connected nodes once
+/// regardless of the direction and number of edges between them. Self loops
+/// can't be stored.
+pub fn write_graph6<N, E>(
+    graph: &graph::DiGraph<N, E>,
+    header: bool,
+) -> Result<String, GraphError> {
+    let mut pairs = Vec::with_capacity(graph.edge_count());
+    for (index, (source, target)) in edge_pairs(graph).enumerate() {
+        if source == target {
+            return Err(GraphError::new(
+                "graph6 can't store self loops, use sparse6 or digraph6",
+                GraphItemType::Edge,
+                index as u32,
+            ));
+        }
+        pairs.push((source.max(target), source.min(target)));
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    let mut pairs = pairs.into_iter().peekable();
+    let n = graph.node_count() as u64;
+    let mut writer = BitWriter::new(start_text(Graph6Format::Graph6, header, graph.node_count()));
+    for j in 1..n {
+        for i in 0..j {
+            let connected = pairs.next_if_eq(&(j, i)).is_some();
+            writer.push(connected);
+        }
+    }
+    Ok(writer.finish(false))
+}
+
+/// Encode a graph as sparse6, which keeps self loops and parallel edges but
+/// not the direction of edges.
+pub fn write_sparse6<N, E>(graph: &graph::DiGraph<N, E>, header: bool) -> String {
+    let mut pairs: Vec<(u64, u64)> = edge_pairs(graph)
+        .map(|(source, target)| (source.max(target), source.min(target)))
+        .collect();
+    pairs.sort_unstable();
+    let n = graph.node_count() as u64;
+    let k = index_bits(n);
+    let mut writer = BitWriter::new(start_text(
+        Graph6Format::Sparse6,
+        header,
+        graph.node_count(),
+    ));
+    let mut last = 0;
+    for (j, i) in pairs {
+        if j == last {
+            writer.push(false);
+        } else if j == last + 1 {
+            writer.push(true);
+        } else {
+            writer.push(true);
+            writer.push_bits(j, k);
+            writer.push(false);
+        }
+        writer.push_bits(i, k);
+        last = j;
+    }
+    // Padding with 1 bits would read as a self loop on the last node if the
+    // current node is the second to last and a node index is all 1 bits.
+    let padding = writer.padding();
+    if k < 6 && n == 1 << k && last + 2 == n && padding > k {
+        writer.push(false);
+    }
+    writer.finish(true)
+}
+
+/// Encode a graph as digraph6, which keeps self loops but stores parallel
+/// edges once.
+pub fn write_digraph6<N, E>(graph: &graph::DiGraph<N, E>, header: bool) -> String {
+    let mut pairs: Vec<(u64, u64)> = edge_pairs(graph).collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    let mut pairs = pairs.into_iter().peekable();
+    let n = graph.node_count() as u64;
+    let mut writer = BitWriter::new(start_text(
+        Graph6Format::Digraph6,
+        header,
+        graph.node_count(),
+    ));
+    for i in 0..n {
+        for j in 0..n {
+            let connected = pairs.next_if_eq(&(i, j)).is_some();
+            writer.push(connected);
+        }
+    }
+    writer.finish(false)
+}
+
+/// Decode a graph in any of the three formats, detected by the first
+/// character, with an optional header. Nodes and edges have `null` weights,
+/// and undirected edges point from the smaller to the larger node index.
+/// Errors are located at `line` and the column of the invalid character.
+pub fn read_graph6(text: &str, line: u32) -> Result<JsonGraph, GraphError> {
+    let text = text.trim_end();
+    let bytes = text.as_bytes();
+    let header = FORMATS
+        .iter()
+        .copied()
+        .find(|format| text.starts_with(format.header()));
+    let start = header.map_or(0, |format| format.header().len());
+    let (format, start) = match bytes.get(start) {
+        Some(b':') => (Graph6Format::Sparse6, start + 1),
+        Some(b'&') => (Graph6Format::Digraph6, start + 1),
+        _ => (Graph6Format::Graph6, start),
+    };
+    let invalid = |message: &str, offset: usize| {
+        GraphError::from_message(&format!("Invalid {}: {}", format.name(), message))
+            .with_location(line, offset as u32 + 1)
+    };
+    if let Some(header) = header.filter(|&header| header != format) {
+        return Err(invalid(
+            &format!("`{}` header on {} data", header.header(), format.name()),
+            0,
+        ));
+    }
+    if bytes.get(start) == Some(&b';') {
+        return Err(invalid("incremental sparse6 isn't supported", start));
+    }
+    let data = &bytes[start..];
+    if let Some(offset) = data.iter().position(|byte| !(63..=126).contains(byte)) {
+        let c = text[start + offset..].chars().next().unwrap_or_default();
+        return Err(invalid(
+            &format!("unexpected character `{}`", c),
+            start + offset,
+        ));
+    }
+    let (n, size_length) =
+        read_node_count(data).ok_or_else(|| invalid("missing node count", start))?;
+    if n >= u64::from(u32::MAX) {
+        return Err(invalid(&format!("too many nodes ({})", n), start));
+    }
+    let data = &data[size_length..];
+    let data_offset = start + size_length;
+    // Check the node count against the data before allocating the nodes.
+    // graph6 and digraph6 store a bit for every pair of nodes, sparse6 has no
+    // such bound so its node count is limited instead.
+    let expected_bits = match format {
+        Graph6Format::Graph6 => Some(n * n.saturating_sub(1) / 2),
+        Graph6Format::Digraph6 => Some(n * n),
+        Graph6Format::Sparse6 => None,
+    };
+    match expected_bits {
+        Some(bits) if data.len() as u64 != bits.div_ceil(6) => {
+            let message = format!(
+                "expected {} characters of edges for {} nodes, found {}",
+                bits.div_ceil(6),
+                n,
+                data.len()
+            );
+            return Err(invalid(&message, data_offset));
+        }
+        None if n > MAX_SPARSE6_NODES => {
+            return Err(invalid(&format!("too many nodes ({})", n), start));
+        }
+        _ => {}
+    }
+    let mut reader = BitReader { data, position: 0 };
+    let mut graph = JsonGraph::with_capacity(n as usize, 0);
+    for _ in 0..n {
+        graph.add_node(Value::Null);
+    }
+    let mut add_edge = |source: u64, target: u64| {
+        graph.add_edge(
+            NodeIndex::new(source as usize),
+            NodeIndex::new(target as usize),
+            Value::Null,
+        );
+    };
+    match format {
+        Graph6Format::Graph6 => {
+            for j in 1..n {
+                for i in 0..j {
+                    if reader.bit() {
+                        add_edge(i, j);
+                    }
+                }
+            }
+        }
+        Graph6Format::Digraph6 => {
+            for i in 0..n {
+                for j in 0..n {
+                    if reader.bit() {
+                        add_edge(i, j);
+                    }
+                }
+            }
+        }
+        Graph6Format::Sparse6 => {
+            let k = index_bits(n);
+            let mut v = 0;
+            while v < n && reader.remaining() > u64::from(k) {
+                if reader.bit() {
+                    v += 1;
+                }
+                let x = reader.bits(k);
+                if x > v {
+                    v = x;
+                } else if v < n {
+                    add_edge(x, v);
+                }
+            }
+        }
+    }
+    Ok(graph)
+}
+
+/// Decode every graph of a file with one graph per line, skipping empty
+/// lines.
+pub fn read_graph6_lines(text: &str) -> Result<Vec<JsonGraph>, GraphError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| read_graph6(line, index as u32 + 1))
+        .collect()
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Encode the structure of the graph as graph6. Every pair of connected
+    /// nodes is stored once, ignoring the direction and number of edges
+    /// between them. Graphs with self loops throw, use `toSparse6` or
+    /// `toDigraph6` for them. Options:
+    ///
+    /// * `header` - start with `>>graph6<<`, defaults to `false`.
+    ///
+    /// Files of several graphs are the encodings joined by line breaks.
+    #[wasm_bindgen(js_name = toGraph6)]
+    pub fn to_graph6(&self, options: JsValue) -> Result<String, JsValue> {
+        let header = get_bool_option(&options, "header", false)?;
+        write_graph6(&self.graph, header).map_err(|err| err.to_js_value())
+    }
+
+    /// Encode the structure of the graph as sparse6, keeping self loops and
+    /// parallel edges but not the direction of edges. Takes the `header`
+    /// option of `toGraph6`.
+    #[wasm_bindgen(js_name = toSparse6)]
+    pub fn to_sparse6(&self, options: JsValue) -> Result<String, JsValue> {
+        let header = get_bool_option(&options, "header", false)?;
+        Ok(write_sparse6(&self.graph, header))
+    }
+
+    /// Encode the structure of the graph as digraph6, keeping self loops but
+    /// storing parallel edges once. Takes the `header` option of `toGraph6`.
+    #[wasm_bindgen(js_name = toDigraph6)]
+    pub fn to_digraph6(&self, options: JsValue) -> Result<String, JsValue> {
+        let header = get_bool_option(&options, "header", false)?;
+        Ok(write_digraph6(&self.graph, header))
+    }
+
+    /// Create a graph from a graph6, sparse6 or digraph6 string, detected by
+    /// its first character and optionally preceded by a header such as
+    /// `>>graph6<<`. Nodes and edges have `null` weights. Undirected edges
+    /// point from the smaller to the larger node index. sparse6 graphs may
+    /// have at most 1048576 nodes, as their size isn't bounded by the data.
+    ///
+    /// Errors have a `location` with the column of the invalid character.
+    #[wasm_bindgen(js_name = fromGraph6)]
+    pub fn from_graph6(text: &str) -> Result<DiGraph, JsValue> {
+        let text = text.trim();
+        if text.contains('\n') {
+            return Err(GraphError::from_message(
+                "Expected a single graph, use `fromGraph6Lines` for several",
+            )
+            .to_js_value());
+        }
+        read_graph6(text, 1)
+            .and_then(|graph| DiGraph::from_json_graph(&graph))
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Create an array of graphs from a file in any of the formats of
+    /// `fromGraph6` with one graph per line. Empty lines are skipped and
+    /// errors have a `location` with the line and column.
+    #[wasm_bindgen(js_name = fromGraph6Lines)]
+    pub fn from_graph6_lines(text: &str) -> Result<Array, JsValue> {
+        let graphs = read_graph6_lines(text).map_err(|err| err.to_js_value())?;
+        let array = Array::new();
+        for graph in &graphs {
+            let graph = DiGraph::from_json_graph(graph).map_err(|err| err.to_js_value())?;
+            array.push(&JsValue::from(graph));
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn structure(n: usize, edges: &[(usize, usize)]) -> JsonGraph {
+        let mut graph = JsonGraph::new();
+        for _ in 0..n {
+            graph.add_node(Value::Null);
+        }
+        for &(source, target) in edges {
+            graph.add_edge(NodeIndex::new(source), NodeIndex::new(target), Value::Null);
+        }
+        graph
+    }
+
+    fn endpoints(graph: &JsonGraph) -> Vec<(usize, usize)> {
+        graph
+            .raw_edges()
+            .iter()
+            .map(|e| (e.source().index(), e.target().index()))
+            .collect()
+    }
+
+    #[test]
+    fn can_encode_node_counts() {
+        for &n in &[0, 62, 63, 258_047, 258_048, 1 << 35] {
+            let mut text = String::new();
+            push_node_count(&mut text, n);
+            assert_eq!(read_node_count(text.as_bytes()), Some((n, text.len())));
+        }
+        let mut text = String::new();
+        push_node_count(&mut text, 63);
+        assert_eq!(text, "~??~");
+    }
+
+    #[test]
+    fn can_read_and_write_graph6() {
+        let graph = structure(5, &[(2, 0), (0, 4), (1, 3), (3, 4), (3, 1)]);
+        assert_eq!(write_graph6(&graph, false).unwrap(), "DQc");
+        assert_eq!(write_graph6(&graph, true).unwrap(), ">>graph6<<DQc");
+        let decoded = read_graph6(">>graph6<<DQc\r\n", 1).unwrap();
+        assert_eq!(decoded.node_count(), 5);
+        assert_eq!(endpoints(&decoded), vec![(0, 2), (1, 3), (0, 4), (3, 4)]);
+        assert_eq!(
+            write_graph6(
+                &structure(4, &[(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]),
+                false
+            )
+            .unwrap(),
+            "C~"
+        );
+        assert_eq!(
+            write_graph6(&structure(2, &[(0, 1), (1, 1)]), false).unwrap_err(),
+            GraphError::new(
+                "graph6 can't store self loops, use sparse6 or digraph6",
+                GraphItemType::Edge,
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn can_read_and_write_sparse6() {
+        let graph = structure(7, &[(0, 1), (0, 2), (1, 2), (5, 6)]);
+        assert_eq!(write_sparse6(&graph, false), ":Fa@x^");
+        let decoded = read_graph6(":Fa@x^", 1).unwrap();
+        assert_eq!(endpoints(&decoded), endpoints(&graph));
+
+        let graph = structure(4, &[(0, 0), (1, 2), (2, 1), (0, 2)]);
+        let decoded = read_graph6(&write_sparse6(&graph, true), 1).unwrap();
+        assert_eq!(endpoints(&decoded), vec![(0, 0), (0, 2), (1, 2), (1, 2)]);
+        // The padding must not read as a self loop on the last node.
+        for &n in &[2, 4, 8, 16] {
+            let graph = structure(n, &[(0, n - 2)]);
+            let decoded = read_graph6(&write_sparse6(&graph, false), 1).unwrap();
+            assert_eq!(endpoints(&decoded), vec![(0, n - 2)]);
+        }
+    }
+
+    #[test]
+    fn can_read_and_write_digraph6() {
+        let graph = structure(5, &[(0, 2), (0, 4), (3, 1), (3, 4)]);
+        assert_eq!(write_digraph6(&graph, false), "&DI?AO?");
+        let decoded = read_graph6(">>digraph6<<&DI?AO?", 1).unwrap();
+        assert_eq!(endpoints(&decoded), endpoints(&graph));
+        let loops = structure(2, &[(1, 1), (1, 0), (1, 0)]);
+        let decoded = read_graph6(&write_digraph6(&loops, false), 1).unwrap();
+        assert_eq!(endpoints(&decoded), vec![(1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn can_read_lines() {
+        let graphs = read_graph6_lines("A_\n\n:A~\n&AO\n").unwrap();
+        let edges: Vec<_> = graphs.iter().map(endpoints).collect();
+        assert_eq!(edges, vec![vec![(0, 1)], vec![(1, 1)], vec![(0, 1)]]);
+        assert_eq!(
+            read_graph6_lines("A_\n\nA_ \nA_?\n").unwrap_err(),
+            GraphError::from_message(
+                "Invalid graph6: expected 1 characters of edges for 2 nodes, found 2"
+            )
+            .with_location(4, 2)
+        );
+    }
+
+    #[test]
+    fn can_report_invalid_graph6() {
+        let error = |text: &str| read_graph6(text, 1).unwrap_err();
+        assert_eq!(
+            error("D Qc"),
+            GraphError::from_message("Invalid graph6: unexpected character ` `")
+                .with_location(1, 2)
+        );
+        assert_eq!(
+            error(":"),
+            GraphError::from_message("Invalid sparse6: missing node count").with_location(1, 2)
+        );
+        assert_eq!(
+            error(">>sparse6<<DQc"),
+            GraphError::from_message("Invalid graph6: `>>sparse6<<` header on graph6 data")
+                .with_location(1, 1)
+        );
+        assert_eq!(
+            error(";Fa@x^"),
+            GraphError::from_message("Invalid graph6: incremental sparse6 isn't supported")
+                .with_location(1, 1)
+        );
+        assert_eq!(
+            error(":~~??~~~~~"),
+            GraphError::from_message("Invalid sparse6: too many nodes (16777215)")
+                .with_location(1, 2)
+        );
+        assert_eq!(
+            error("~~??~~~~~"),
+            GraphError::from_message(
+                "Invalid graph6: expected 23456243864918 characters of edges for \
+                 16777215 nodes, found 1"
+            )
+            .with_location(1, 9)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_graph6() {
+        let g = DiGraph::from_graph6("DQc\n").unwrap();
+        assert_eq!(g.edge_count(), 4);
+        assert_eq!(g.to_graph6(JsValue::UNDEFINED).unwrap(), "DQc");
+        assert_eq!(DiGraph::from_graph6_lines("A_\n:An").unwrap().length(), 2);
+        assert!(DiGraph::from_graph6("A_\nA_").is_err());
+    }
+}
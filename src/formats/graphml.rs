@@ -0,0 +1,699 @@
+//! GraphML import and export. Weight objects map to `<data>` elements, with
+//! a typed `<key>` declaration for every property name, so that tools such as
+//! Gephi and yEd can read them as node and edge attributes.
+
+use crate::formats::json::JsonGraph;
+use crate::formats::node_ids;
+use crate::graph_impl::{DiGraph, UnGraph};
+use crate::js_helpers::options::get_string_option;
+use crate::GraphError;
+use petgraph::graph::NodeIndex;
+use roxmltree::{Document, Node};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Value types of GraphML `attr.type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+}
+
+impl KeyType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "boolean" => Some(KeyType::Boolean),
+            "int" => Some(KeyType::Int),
+            "long" => Some(KeyType::Long),
+            "float" => Some(KeyType::Float),
+            "double" => Some(KeyType::Double),
+            "string" => Some(KeyType::String),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyType::Boolean => "boolean",
+            KeyType::Int => "int",
+            KeyType::Long => "long",
+            KeyType::Float => "float",
+            KeyType::Double => "double",
+            KeyType::String => "string",
+        }
+    }
+
+    /// The narrowest type which can hold a JSON value. Objects and arrays are
+    /// written as JSON strings.
+    fn of_value(value: &Value) -> Self {
+        match value {
+            Value::Bool(_) => KeyType::Boolean,
+            Value::Number(number) if number.is_i64() => KeyType::Long,
+            Value::Number(_) => KeyType::Double,
+            _ => KeyType::String,
+        }
+    }
+
+    /// The narrowest type which can hold values of both types.
+    fn merge(self, other: KeyType) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (KeyType::Long, KeyType::Double) | (KeyType::Double, KeyType::Long) => KeyType::Double,
+            _ => KeyType::String,
+        }
+    }
+
+    fn parse_value(self, text: &str) -> Option<Value> {
+        let trimmed = text.trim();
+        match self {
+            KeyType::Boolean => match trimmed {
+                "1" => Some(Value::Bool(true)),
+                "0" => Some(Value::Bool(false)),
+                _ if trimmed.eq_ignore_ascii_case("true") => Some(Value::Bool(true)),
+                _ if trimmed.eq_ignore_ascii_case("false") => Some(Value::Bool(false)),
+                _ => None,
+            },
+            KeyType::Int | KeyType::Long => trimmed.parse::<i64>().ok().map(Value::from),
+            KeyType::Float | KeyType::Double => trimmed.parse::<f64>().ok().map(Value::from),
+            KeyType::String => Some(Value::from(text)),
+        }
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Properties written for a weight. Objects give their non-null properties,
+/// other non-null weights are written as a single `weight` property.
+fn weight_properties(weight: &Value) -> Vec<(&str, &Value)> {
+    match weight {
+        Value::Null => Vec::new(),
+        Value::Object(object) => object
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| (key.as_str(), value))
+            .collect(),
+        _ => vec![("weight", weight)],
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        _ => value.to_string(),
+    }
+}
+
+struct KeyDeclaration<'a> {
+    domain: &'static str,
+    name: &'a str,
+    key_type: KeyType,
+}
+
+/// Declarations for all properties of the given weights, in order of first
+/// appearance.
+fn declare_keys<'a>(
+    keys: &mut Vec<KeyDeclaration<'a>>,
+    key_ids: &mut HashMap<(&'static str, &'a str), usize>,
+    domain: &'static str,
+    properties: &[(&'a str, &'a Value)],
+) {
+    for &(name, value) in properties {
+        let key_type = KeyType::of_value(value);
+        match key_ids.get(&(domain, name)) {
+            Some(&index) => keys[index].key_type = keys[index].key_type.merge(key_type),
+            None => {
+                key_ids.insert((domain, name), keys.len());
+                keys.push(KeyDeclaration {
+                    domain,
+                    name,
+                    key_type,
+                });
+            }
+        }
+    }
+}
+
+/// Write a graph as GraphML with the node ids from `node_ids`. The
+/// `id_property` isn't written as data.
+pub fn write_graphml(
+    graph: &JsonGraph,
+    directed: bool,
+    id_property: Option<&str>,
+) -> Result<String, GraphError> {
+    let node_ids = node_ids(graph, id_property)?;
+    let node_properties: Vec<_> = graph
+        .raw_nodes()
+        .iter()
+        .map(|node| {
+            let mut properties = weight_properties(&node.weight);
+            properties.retain(|(name, _)| Some(*name) != id_property);
+            properties
+        })
+        .collect();
+    let edge_properties: Vec<_> = graph
+        .raw_edges()
+        .iter()
+        .map(|edge| weight_properties(&edge.weight))
+        .collect();
+
+    let mut keys = Vec::new();
+    let mut key_ids = HashMap::new();
+    for properties in &node_properties {
+        declare_keys(&mut keys, &mut key_ids, "node", properties);
+    }
+    for properties in &edge_properties {
+        declare_keys(&mut keys, &mut key_ids, "edge", properties);
+    }
+
+    // Writing to a String can't fail.
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+         http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+    );
+    for (index, key) in keys.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  <key id=\"d{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+            index,
+            key.domain,
+            escape_xml(key.name),
+            key.key_type.name()
+        );
+    }
+    let _ = writeln!(
+        out,
+        "  <graph id=\"G\" edgedefault=\"{}\">",
+        if directed { "directed" } else { "undirected" }
+    );
+    let write_data = |out: &mut String, domain, properties: &[(&str, &Value)]| {
+        for (name, value) in properties {
+            let _ = writeln!(
+                out,
+                "      <data key=\"d{}\">{}</data>",
+                key_ids[&(domain, *name)],
+                escape_xml(&value_text(value))
+            );
+        }
+    };
+    for (id, properties) in node_ids.iter().zip(&node_properties) {
+        if properties.is_empty() {
+            let _ = writeln!(out, "    <node id=\"{}\"/>", escape_xml(id));
+        } else {
+            let _ = writeln!(out, "    <node id=\"{}\">", escape_xml(id));
+            write_data(&mut out, "node", properties);
+            out.push_str("    </node>\n");
+        }
+    }
+    for (index, (edge, properties)) in graph.raw_edges().iter().zip(&edge_properties).enumerate() {
+        let _ = write!(
+            out,
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"",
+            index,
+            escape_xml(&node_ids[edge.source().index()]),
+            escape_xml(&node_ids[edge.target().index()])
+        );
+        if properties.is_empty() {
+            out.push_str("/>\n");
+        } else {
+            out.push_str(">\n");
+            write_data(&mut out, "edge", properties);
+            out.push_str("    </edge>\n");
+        }
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    Ok(out)
+}
+
+/// A `<key>` declaration read from a GraphML document.
+struct Key {
+    domain: String,
+    name: String,
+    key_type: KeyType,
+    default: Option<Value>,
+}
+
+impl Key {
+    fn applies_to(&self, domain: &str) -> bool {
+        self.domain == domain || self.domain == "all"
+    }
+}
+
+/// A parsed GraphML graph, where `directed` is the graph's `edgedefault`.
+/// Edges of undirected graphs keep the direction of their `source` and
+/// `target`.
+pub struct GraphMl {
+    pub directed: bool,
+    pub graph: JsonGraph,
+}
+
+fn error_at(doc: &Document, node: Node, message: &str) -> GraphError {
+    let position = doc.text_pos_at(node.range().start);
+    GraphError::from_message(&format!("Invalid GraphML: {}", message))
+        .with_location(position.row, position.col)
+}
+
+fn required_attribute<'a>(
+    doc: &Document,
+    node: Node<'a, '_>,
+    name: &str,
+) -> Result<&'a str, GraphError> {
+    node.attribute(name).ok_or_else(|| {
+        error_at(
+            doc,
+            node,
+            &format!("`{}` is missing a `{}`", node.tag_name().name(), name),
+        )
+    })
+}
+
+fn child_elements<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn read_keys(doc: &Document) -> Result<HashMap<String, Key>, GraphError> {
+    let mut keys = HashMap::new();
+    for key in child_elements(doc.root_element(), "key") {
+        let id = required_attribute(doc, key, "id")?;
+        let type_name = key.attribute("attr.type").unwrap_or("string");
+        let key_type = KeyType::from_name(type_name)
+            .ok_or_else(|| error_at(doc, key, &format!("Unknown key type `{}`", type_name)))?;
+        let default = match child_elements(key, "default").next() {
+            None => None,
+            Some(default) => {
+                let text = default.text().unwrap_or("");
+                Some(key_type.parse_value(text).ok_or_else(|| {
+                    error_at(
+                        doc,
+                        default,
+                        &format!("Invalid {} value `{}`", type_name, text),
+                    )
+                })?)
+            }
+        };
+        keys.insert(
+            String::from(id),
+            Key {
+                domain: String::from(key.attribute("for").unwrap_or("all")),
+                name: String::from(key.attribute("attr.name").unwrap_or(id)),
+                key_type,
+                default,
+            },
+        );
+    }
+    Ok(keys)
+}
+
+/// Read the weight object of a node or edge from its key defaults and `<data>`
+/// children. Data with nested XML content, such as yEd graphics, is skipped.
+fn read_weight(
+    doc: &Document,
+    element: Node,
+    domain: &str,
+    keys: &HashMap<String, Key>,
+) -> Result<Map<String, Value>, GraphError> {
+    let mut weight = Map::new();
+    for key in keys.values() {
+        if let (true, Some(default)) = (key.applies_to(domain), &key.default) {
+            weight.insert(key.name.clone(), default.clone());
+        }
+    }
+    for data in child_elements(element, "data") {
+        let id = required_attribute(doc, data, "key")?;
+        let key = match keys.get(id) {
+            Some(key) if key.applies_to(domain) => key,
+            _ => {
+                return Err(error_at(
+                    doc,
+                    data,
+                    &format!("Unknown {} key `{}`", domain, id),
+                ))
+            }
+        };
+        if data.children().any(|child| child.is_element()) {
+            continue;
+        }
+        let text: String = data.children().filter_map(|child| child.text()).collect();
+        let value = key.key_type.parse_value(&text).ok_or_else(|| {
+            error_at(
+                doc,
+                data,
+                &format!(
+                    "Invalid {} value `{}` for key `{}`",
+                    key.key_type.name(),
+                    text,
+                    key.name
+                ),
+            )
+        })?;
+        weight.insert(key.name.clone(), value);
+    }
+    Ok(weight)
+}
+
+/// Parse the first graph of a GraphML document. Node and edge weights are
+/// objects of their data, and if `id_property` is given nodes also get their
+/// GraphML id as that property. Every edge must have the direction given by
+/// `directed`, either from the `edgedefault` or its own `directed`
+/// attribute. Nested graphs, hyperedges and ports are ignored.
+pub fn parse_graphml(
+    text: &str,
+    id_property: Option<&str>,
+    directed: bool,
+) -> Result<GraphMl, GraphError> {
+    let doc = Document::parse(text).map_err(|err| {
+        let position = err.pos();
+        GraphError::from_message(&format!("Invalid GraphML: {}", err))
+            .with_location(position.row, position.col)
+    })?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "graphml" {
+        return Err(error_at(&doc, root, "Expected a `graphml` root element"));
+    }
+    let keys = read_keys(&doc)?;
+    let graph_element = child_elements(root, "graph")
+        .next()
+        .ok_or_else(|| error_at(&doc, root, "Expected a `graph` element"))?;
+    let edge_default = match graph_element.attribute("edgedefault") {
+        None | Some("directed") => true,
+        Some("undirected") => false,
+        Some(other) => {
+            return Err(error_at(
+                &doc,
+                graph_element,
+                &format!("Unknown edge default `{}`", other),
+            ))
+        }
+    };
+
+    let mut graph = JsonGraph::new();
+    let mut node_indices = HashMap::new();
+    for node in child_elements(graph_element, "node") {
+        let id = required_attribute(&doc, node, "id")?;
+        let mut weight = read_weight(&doc, node, "node", &keys)?;
+        if let Some(id_property) = id_property {
+            weight.insert(String::from(id_property), Value::from(id));
+        }
+        let index = graph.add_node(Value::Object(weight));
+        if node_indices.insert(id, index).is_some() {
+            return Err(error_at(&doc, node, &format!("Duplicate node id `{}`", id)));
+        }
+    }
+    // Edges may reference nodes declared after them, so they are added once
+    // all nodes are known.
+    for edge in child_elements(graph_element, "edge") {
+        let edge_directed = match edge.attribute("directed") {
+            None => edge_default,
+            Some("true") => true,
+            Some("false") => false,
+            Some(other) => {
+                return Err(error_at(
+                    &doc,
+                    edge,
+                    &format!("Unknown edge direction `{}`", other),
+                ))
+            }
+        };
+        if edge_directed != directed {
+            let message = if directed {
+                "Undirected edges are not supported by `DiGraph`, use `UnGraph.fromGraphML`"
+            } else {
+                "Directed edges are not supported by `UnGraph`, use `DiGraph.fromGraphML`"
+            };
+            return Err(error_at(&doc, edge, message));
+        }
+        let mut endpoints = [NodeIndex::new(0); 2];
+        for (endpoint, name) in endpoints.iter_mut().zip(&["source", "target"]) {
+            let id = required_attribute(&doc, edge, name)?;
+            *endpoint = *node_indices
+                .get(id)
+                .ok_or_else(|| error_at(&doc, edge, &format!("Unknown node `{}`", id)))?;
+        }
+        let weight = read_weight(&doc, edge, "edge", &keys)?;
+        graph.add_edge(endpoints[0], endpoints[1], Value::Object(weight));
+    }
+    Ok(GraphMl {
+        directed: edge_default,
+        graph,
+    })
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Export the graph as directed GraphML. Object weights are written as
+    /// `<data>` for each non-null property, with a `<key>` declaration whose
+    /// type is inferred from the values: `boolean`, `long`, `double` or
+    /// `string`. Nested objects and arrays are written as JSON strings and
+    /// other weights as a single `weight` property. Options:
+    ///
+    /// * `idProperty` - node weight property used as the GraphML node id,
+    ///   instead of `n0`, `n1`, ... by index.
+    #[wasm_bindgen(js_name = toGraphML)]
+    pub fn to_graphml(&self, options: JsValue) -> Result<String, JsValue> {
+        let id_property = get_string_option(&options, "idProperty")?;
+        self.to_json_graph()
+            .and_then(|graph| write_graphml(&graph, true, id_property.as_deref()))
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Create a graph from the first graph of a GraphML document. Node and
+    /// edge weights are objects with a property for each `<data>` element,
+    /// named by the `attr.name` of its key and converted to the key's
+    /// `attr.type`, including key defaults. Options:
+    ///
+    /// * `idProperty` - node weight property to store the GraphML node id in.
+    ///
+    /// Malformed documents throw a `GraphError` with its `location`, as do
+    /// undirected edges, which are read with `UnGraph.fromGraphML`.
+    #[wasm_bindgen(js_name = fromGraphML)]
+    pub fn from_graphml(text: &str, options: JsValue) -> Result<DiGraph, JsValue> {
+        let id_property = get_string_option(&options, "idProperty")?;
+        parse_graphml(text, id_property.as_deref(), true)
+            .and_then(|graphml| DiGraph::from_json_graph(&graphml.graph))
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[wasm_bindgen]
+impl UnGraph {
+    /// Export the graph as undirected GraphML, see `DiGraph.toGraphML`.
+    #[wasm_bindgen(js_name = toGraphML)]
+    pub fn to_graphml(&self, options: JsValue) -> Result<String, JsValue> {
+        let id_property = get_string_option(&options, "idProperty")?;
+        self.to_directed()
+            .to_json_graph()
+            .and_then(|graph| write_graphml(&graph, false, id_property.as_deref()))
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Create an undirected graph from the first graph of a GraphML document,
+    /// see `DiGraph.fromGraphML`. Directed edges throw a `GraphError`.
+    #[wasm_bindgen(js_name = fromGraphML)]
+    pub fn from_graphml(text: &str, options: JsValue) -> Result<UnGraph, JsValue> {
+        let id_property = get_string_option(&options, "idProperty")?;
+        parse_graphml(text, id_property.as_deref(), false)
+            .and_then(|graphml| DiGraph::from_json_graph(&graphml.graph))
+            .map(|g| UnGraph::from(g.graph.into_edge_type()))
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use crate::GraphItemType;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    fn new_json_graph() -> JsonGraph {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({ "name": "a & b", "size": 2, "tags": ["x"] }));
+        let b = graph.add_node(json!({ "name": "c", "size": 2.5, "visible": true }));
+        let c = graph.add_node(json!("plain"));
+        graph.add_edge(a, b, json!({ "weight": 1 }));
+        graph.add_edge(b, c, Value::Null);
+        graph
+    }
+
+    #[test]
+    fn can_write_graphml() {
+        let text = write_graphml(&new_json_graph(), false, None).unwrap();
+        assert!(text.contains(
+            "  <key id=\"d0\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"d1\" for=\"node\" attr.name=\"size\" attr.type=\"double\"/>\n\
+             \x20 <key id=\"d2\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"d3\" for=\"node\" attr.name=\"visible\" attr.type=\"boolean\"/>\n\
+             \x20 <key id=\"d4\" for=\"node\" attr.name=\"weight\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"d5\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>\n\
+             \x20 <graph id=\"G\" edgedefault=\"undirected\">\n"
+        ));
+        assert!(text.contains("      <data key=\"d0\">a &amp; b</data>\n"));
+        assert!(text.contains("      <data key=\"d2\">[&quot;x&quot;]</data>\n"));
+        assert!(text.contains("    <edge id=\"e1\" source=\"n1\" target=\"n2\"/>\n"));
+    }
+
+    #[test]
+    fn can_write_graphml_with_node_ids() {
+        let mut graph = new_json_graph();
+        graph[NodeIndex::new(2)] = json!({ "name": "d" });
+        let text = write_graphml(&graph, true, Some("name")).unwrap();
+        assert!(text.contains("    <node id=\"a &amp; b\">\n"));
+        assert!(text.contains("    <node id=\"d\"/>\n"));
+        assert!(!text.contains("attr.name=\"name\""));
+        let err = write_graphml(&new_json_graph(), true, Some("size")).unwrap_err();
+        assert_eq!(
+            err,
+            GraphError::new(
+                "Node weight is missing a string or number `size` property",
+                GraphItemType::Node,
+                2
+            )
+        );
+    }
+
+    #[test]
+    fn can_round_trip_graphml() {
+        let graph = new_json_graph();
+        let text = write_graphml(&graph, true, None).unwrap();
+        let graphml = parse_graphml(&text, None, true).unwrap();
+        assert!(graphml.directed);
+        assert_eq!(
+            graphml.graph.raw_nodes()[0].weight,
+            json!({ "name": "a & b", "size": 2.0, "tags": "[\"x\"]" })
+        );
+        assert_eq!(
+            graphml.graph.raw_nodes()[2].weight,
+            json!({ "weight": "plain" })
+        );
+        assert_eq!(graphml.graph.raw_edges()[0].weight, json!({ "weight": 1 }));
+        assert_eq!(graphml.graph.raw_edges()[1].weight, json!({}));
+        assert_eq!(graphml.graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn can_parse_graphml() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:y="http://www.yworks.com/xml/graphml">
+  <key id="color" for="node" attr.name="color" attr.type="string">
+    <default>yellow</default>
+  </key>
+  <key id="w" for="edge" attr.name="weight" attr.type="double"/>
+  <key id="g" for="node" yfiles.type="nodegraphics"/>
+  <key id="f" for="all" attr.name="flag" attr.type="boolean"/>
+  <graph id="G" edgedefault="undirected">
+    <edge source="n1" target="n0"><data key="w">1.5</data></edge>
+    <node id="n0"><data key="color">green</data><data key="f">1</data></node>
+    <node id="n1"><data key="g"><y:ShapeNode/></data></node>
+  </graph>
+</graphml>"#;
+        let graphml = parse_graphml(text, Some("id"), false).unwrap();
+        assert!(!graphml.directed);
+        let graph = graphml.graph;
+        assert_eq!(
+            graph.raw_nodes()[0].weight,
+            json!({ "id": "n0", "color": "green", "flag": true })
+        );
+        assert_eq!(
+            graph.raw_nodes()[1].weight,
+            json!({ "id": "n1", "color": "yellow" })
+        );
+        assert_eq!(graph.raw_edges()[0].source(), NodeIndex::new(1));
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "weight": 1.5 }));
+    }
+
+    #[test]
+    fn can_report_graphml_errors() {
+        let error = |text: &str| parse_graphml(text, None, true).err().unwrap();
+        assert_eq!(
+            error(
+                "<graphml>\n  <graph>\n    <edge source=\"a\" target=\"b\"/>\n</graph></graphml>"
+            ),
+            GraphError::from_message("Invalid GraphML: Unknown node `a`").with_location(3, 5)
+        );
+        assert_eq!(
+            error(
+                "<graphml><key id=\"k\" for=\"node\" attr.type=\"int\"/>\
+                 <graph><node id=\"a\"><data key=\"k\">x</data></node></graph></graphml>"
+            ),
+            GraphError::from_message("Invalid GraphML: Invalid int value `x` for key `k`")
+                .with_location(1, 70)
+        );
+        assert_eq!(
+            error("<graph/>"),
+            GraphError::from_message("Invalid GraphML: Expected a `graphml` root element")
+                .with_location(1, 1)
+        );
+        assert_eq!(
+            error("<graphml>\n  <graph></grahp>\n</graphml>"),
+            GraphError::from_message("Invalid GraphML: expected 'graph' tag, not 'grahp' at 2:10")
+                .with_location(2, 10)
+        );
+        assert_eq!(
+            error(
+                "<graphml><graph><node id=\"a\"/>\n  \
+                 <edge source=\"a\" target=\"a\" directed=\"false\"/></graph></graphml>"
+            ),
+            GraphError::from_message(
+                "Invalid GraphML: Undirected edges are not supported by `DiGraph`, \
+                 use `UnGraph.fromGraphML`"
+            )
+            .with_location(2, 3)
+        );
+        assert!(parse_graphml(
+            "<graphml><graph edgedefault=\"undirected\"><node id=\"a\"/>\
+             <edge source=\"a\" target=\"a\"/></graph></graphml>",
+            None,
+            true
+        )
+        .is_err());
+        assert!(parse_graphml(
+            "<graphml><graph><node id=\"a\"/>\
+             <edge source=\"a\" target=\"a\"/></graph></graphml>",
+            None,
+            false
+        )
+        .is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_export_and_import_graphml() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let text = g.to_graphml(JsValue::UNDEFINED).unwrap();
+        assert!(text.contains("edgedefault=\"directed\""));
+        let imported = DiGraph::from_graphml(&text, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.node_count(), 5);
+        assert_eq!(imported.edge_endpoints_all(), g.edge_endpoints_all());
+        let text = g.to_undirected().to_graphml(JsValue::UNDEFINED).unwrap();
+        assert!(text.contains("edgedefault=\"undirected\""));
+        assert!(DiGraph::from_graphml(&text, JsValue::UNDEFINED).is_err());
+        let imported = UnGraph::from_graphml(&text, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.edge_count(), g.edge_count());
+    }
+}
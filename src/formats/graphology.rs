@@ -0,0 +1,559 @@
+//! Conversion to and from graphology's serialized graph format, as used by
+//! `graph.export()` and `graph.import(data)`.
+
+use crate::formats::json::JsonGraph;
+use crate::formats::{id_text, node_ids};
+use crate::graph_impl::{DiGraph, GraphPolicy, UnGraph};
+use crate::js_helpers::options::get_string_option;
+use crate::{GraphError, GraphItemType};
+use petgraph::graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphType {
+    Mixed,
+    Directed,
+    Undirected,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SerializedOptions {
+    #[serde(rename = "type")]
+    pub graph_type: GraphType,
+    pub multi: bool,
+    pub allow_self_loops: bool,
+}
+
+impl Default for SerializedOptions {
+    fn default() -> Self {
+        SerializedOptions {
+            graph_type: GraphType::Mixed,
+            multi: false,
+            allow_self_loops: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedNode {
+    pub key: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedEdge {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<Value>,
+    pub source: Value,
+    pub target: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undirected: bool,
+}
+
+/// A graph in graphology's serialized format. Missing fields get graphology's
+/// defaults.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SerializedGraph {
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+    #[serde(default)]
+    pub options: SerializedOptions,
+    #[serde(default)]
+    pub nodes: Vec<SerializedNode>,
+    #[serde(default)]
+    pub edges: Vec<SerializedEdge>,
+}
+
+fn invalid_graph(message: &str) -> GraphError {
+    GraphError::from_message(&format!("Invalid graphology graph: {}", message))
+}
+
+/// Attributes for a weight. Objects are used without `skip`, other non-null
+/// weights are stored as a `weight` attribute.
+fn weight_attributes(weight: &Value, skip: Option<&str>) -> Option<Map<String, Value>> {
+    match weight {
+        Value::Null => None,
+        Value::Object(object) => Some(
+            object
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != skip)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        _ => {
+            let mut attributes = Map::new();
+            attributes.insert(String::from("weight"), weight.clone());
+            Some(attributes)
+        }
+    }
+}
+
+/// Convert a graph to a directed serialized graph. Nodes get their keys from
+/// `node_ids` and edges get the string or number `key_property` of their
+/// weight as key, which must be unique, or else `e0`, `e1`, ... by index,
+/// with a suffix such as `e0_1` if that key is taken. The `multi` and
+/// `allowSelfLoops` options follow the graph's policy.
+pub fn to_graphology(
+    graph: &JsonGraph,
+    policy: GraphPolicy,
+    key_property: Option<&str>,
+) -> Result<SerializedGraph, GraphError> {
+    let keys = node_ids(graph, key_property)?;
+    let nodes = graph
+        .raw_nodes()
+        .iter()
+        .zip(&keys)
+        .map(|(node, key)| SerializedNode {
+            key: Value::from(key.as_str()),
+            attributes: weight_attributes(&node.weight, key_property),
+        })
+        .collect();
+    let weight_keys: Vec<Option<&Value>> = graph
+        .raw_edges()
+        .iter()
+        .map(|edge| {
+            key_property
+                .and_then(|key_property| edge.weight.get(key_property))
+                .filter(|key| id_text(Some(key)).is_some())
+        })
+        .collect();
+    let mut used_keys = HashSet::new();
+    for (index, key) in weight_keys.iter().enumerate() {
+        if let Some(key) = key.and_then(|key| id_text(Some(key))) {
+            if !used_keys.insert(key.clone()) {
+                return Err(GraphError::new(
+                    &format!("Duplicate edge key `{}`", key),
+                    GraphItemType::Edge,
+                    index as u32,
+                ));
+            }
+        }
+    }
+    let edges = graph
+        .raw_edges()
+        .iter()
+        .zip(weight_keys)
+        .enumerate()
+        .map(|(index, (edge, key))| {
+            let serialized_key = match key {
+                Some(key) => key.clone(),
+                None => {
+                    let mut fallback = format!("e{}", index);
+                    let mut suffix = 0;
+                    while used_keys.contains(&fallback) {
+                        suffix += 1;
+                        fallback = format!("e{}_{}", index, suffix);
+                    }
+                    used_keys.insert(fallback.clone());
+                    Value::from(fallback)
+                }
+            };
+            SerializedEdge {
+                key: Some(serialized_key),
+                source: Value::from(keys[edge.source().index()].as_str()),
+                target: Value::from(keys[edge.target().index()].as_str()),
+                attributes: weight_attributes(&edge.weight, key.and(key_property)),
+                undirected: false,
+            }
+        })
+        .collect();
+    Ok(SerializedGraph {
+        attributes: Map::new(),
+        options: SerializedOptions {
+            graph_type: GraphType::Directed,
+            multi: policy.allow_parallel_edges,
+            allow_self_loops: policy.allow_self_loops,
+        },
+        nodes,
+        edges,
+    })
+}
+
+/// Convert a serialized graph to a graph with the policy given by its
+/// options. Node and edge weights are their attribute objects, with the node
+/// and edge keys stored as `key_property` if given. Every edge must have the
+/// direction given by `directed`, either from the graph's `type` or its own
+/// `undirected` flag.
+pub fn from_graphology(
+    serialized: &SerializedGraph,
+    key_property: Option<&str>,
+    directed: bool,
+) -> Result<(JsonGraph, GraphPolicy), GraphError> {
+    let options = &serialized.options;
+    let policy = GraphPolicy {
+        allow_parallel_edges: options.multi,
+        allow_self_loops: options.allow_self_loops,
+        ..GraphPolicy::default()
+    };
+    let mut graph = JsonGraph::with_capacity(serialized.nodes.len(), serialized.edges.len());
+    let mut node_indices = HashMap::new();
+    for (index, node) in serialized.nodes.iter().enumerate() {
+        let key = id_text(Some(&node.key)).ok_or_else(|| {
+            invalid_graph(&format!("node {} must have a string or number key", index))
+        })?;
+        let mut weight = node.attributes.clone().unwrap_or_default();
+        if let Some(key_property) = key_property {
+            weight.insert(String::from(key_property), node.key.clone());
+        }
+        let node_index = graph.add_node(Value::Object(weight));
+        if node_indices.insert(key.clone(), node_index).is_some() {
+            return Err(invalid_graph(&format!("duplicate node key `{}`", key)));
+        }
+    }
+    let mut seen_edges = HashSet::new();
+    let mut edge_keys = HashSet::new();
+    for (index, edge) in serialized.edges.iter().enumerate() {
+        if let Some(key) = &edge.key {
+            let key = id_text(Some(key)).ok_or_else(|| {
+                invalid_graph(&format!("edge {} must have a string or number key", index))
+            })?;
+            if !edge_keys.insert(key.clone()) {
+                return Err(invalid_graph(&format!("duplicate edge key `{}`", key)));
+            }
+        }
+        let mut endpoints = [NodeIndex::new(0); 2];
+        for (endpoint, key) in endpoints.iter_mut().zip(&[&edge.source, &edge.target]) {
+            let key = id_text(Some(key)).ok_or_else(|| {
+                invalid_graph(&format!(
+                    "edge {} must have string or number endpoints",
+                    index
+                ))
+            })?;
+            *endpoint = *node_indices.get(&key).ok_or_else(|| {
+                invalid_graph(&format!("edge {} references unknown node `{}`", index, key))
+            })?;
+        }
+        let edge_directed = match options.graph_type {
+            GraphType::Directed => true,
+            GraphType::Undirected => false,
+            GraphType::Mixed => !edge.undirected,
+        };
+        if edge_directed != directed {
+            return Err(invalid_graph(&format!(
+                "edge {} is {}directed, use `{}.fromGraphology`",
+                index,
+                if edge_directed { "" } else { "un" },
+                if edge_directed { "DiGraph" } else { "UnGraph" }
+            )));
+        }
+        let [source, target] = endpoints;
+        if source == target && !options.allow_self_loops {
+            return Err(invalid_graph(&format!(
+                "edge {} is a self loop but `allowSelfLoops` is false",
+                index
+            )));
+        }
+        let pair = if !directed && target < source {
+            (target, source)
+        } else {
+            (source, target)
+        };
+        if !seen_edges.insert(pair) && !options.multi {
+            return Err(invalid_graph(&format!(
+                "edge {} is a parallel edge but `multi` is false",
+                index
+            )));
+        }
+        let mut weight = edge.attributes.clone().unwrap_or_default();
+        if let (Some(key_property), Some(key)) = (key_property, &edge.key) {
+            weight.insert(String::from(key_property), key.clone());
+        }
+        graph.add_edge(source, target, Value::Object(weight));
+    }
+    Ok((graph, policy))
+}
+
+/// Read serialized graph data and the `keyProperty` option from JS.
+fn read_graphology(
+    data: JsValue,
+    options: &JsValue,
+    directed: bool,
+) -> Result<(JsonGraph, GraphPolicy), JsValue> {
+    let key_property = get_string_option(options, "keyProperty")?;
+    let serialized: SerializedGraph = data
+        .into_serde()
+        .map_err(|err| invalid_graph(&err.to_string()).to_js_value())?;
+    from_graphology(&serialized, key_property.as_deref(), directed).map_err(|err| err.to_js_value())
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Export the graph in graphology's serialized format, which can be
+    /// loaded with `Graph.from(data)`. Object weights become attributes and
+    /// other non-null weights a `weight` attribute. The `multi` and
+    /// `allowSelfLoops` options follow the graph's policy. Options:
+    ///
+    /// * `keyProperty` - weight property used as the node key instead of
+    ///   `n0`, `n1`, ... by index, and as the edge key when an edge weight
+    ///   has it instead of `e0`, `e1`, .... Keys must be unique.
+    #[wasm_bindgen(js_name = toGraphology)]
+    pub fn to_graphology(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let key_property = get_string_option(&options, "keyProperty")?;
+        let serialized = self
+            .to_json_graph()
+            .and_then(|graph| to_graphology(&graph, self.policy, key_property.as_deref()))
+            .map_err(|err| err.to_js_value())?;
+        JsValue::from_serde(&serialized).map_err(|_| JsValue::from_str("Failed to transform graph"))
+    }
+
+    /// Create a graph from graphology's serialized format, such as the result
+    /// of `graph.export()`. Weights are the attribute objects and the graph's
+    /// policy follows the `multi` and `allowSelfLoops` options. Graph
+    /// attributes are ignored and undirected edges throw a `GraphError`, use
+    /// `UnGraph.fromGraphology` for undirected graphs. Options:
+    ///
+    /// * `keyProperty` - weight property to store the node and edge keys in.
+    #[wasm_bindgen(js_name = fromGraphology)]
+    pub fn from_graphology(data: JsValue, options: JsValue) -> Result<DiGraph, JsValue> {
+        let (graph, policy) = read_graphology(data, &options, true)?;
+        let mut graph = DiGraph::from_json_graph(&graph).map_err(|err| err.to_js_value())?;
+        graph.policy = policy;
+        Ok(graph)
+    }
+}
+
+#[wasm_bindgen]
+impl UnGraph {
+    /// Create an undirected graph from graphology's serialized format, see
+    /// `DiGraph.fromGraphology`. The `multi` and `allowSelfLoops` options
+    /// are checked but not kept, and directed edges throw a `GraphError`.
+    #[wasm_bindgen(js_name = fromGraphology)]
+    pub fn from_graphology(data: JsValue, options: JsValue) -> Result<UnGraph, JsValue> {
+        let (graph, _policy) = read_graphology(data, &options, false)?;
+        let graph = DiGraph::from_json_graph(&graph).map_err(|err| err.to_js_value())?;
+        Ok(UnGraph::from(graph.graph.into_edge_type()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    fn round_trip(graph: &JsonGraph, policy: GraphPolicy) -> (JsonGraph, GraphPolicy) {
+        let serialized = to_graphology(graph, policy, None).unwrap();
+        let text = serde_json::to_string(&serialized).unwrap();
+        from_graphology(&serde_json::from_str(&text).unwrap(), None, true).unwrap()
+    }
+
+    fn endpoints(graph: &JsonGraph) -> Vec<(usize, usize)> {
+        graph
+            .raw_edges()
+            .iter()
+            .map(|edge| (edge.source().index(), edge.target().index()))
+            .collect()
+    }
+
+    #[test]
+    fn can_export_graphology() {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({ "name": "a", "size": 1 }));
+        let b = graph.add_node(Value::Null);
+        graph.add_edge(a, b, json!(2.5));
+        let serialized = to_graphology(&graph, GraphPolicy::default(), Some("name"));
+        assert_eq!(
+            serialized.unwrap_err(),
+            GraphError::new(
+                "Node weight is missing a string or number `name` property",
+                GraphItemType::Node,
+                1
+            )
+        );
+        let serialized = to_graphology(&graph, GraphPolicy::default(), None).unwrap();
+        assert_eq!(
+            serde_json::to_value(serialized).unwrap(),
+            json!({
+                "attributes": {},
+                "options": { "type": "directed", "multi": true, "allowSelfLoops": true },
+                "nodes": [
+                    { "key": "n0", "attributes": { "name": "a", "size": 1 } },
+                    { "key": "n1" }
+                ],
+                "edges": [
+                    { "key": "e0", "source": "n0", "target": "n1", "attributes": { "weight": 2.5 } }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn can_round_trip_multigraph() {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({ "name": "a" }));
+        let b = graph.add_node(json!({}));
+        graph.add_edge(a, b, json!({ "w": 1 }));
+        graph.add_edge(a, b, json!({ "w": 2 }));
+        graph.add_edge(b, a, json!({}));
+        let policy = GraphPolicy {
+            allow_self_loops: false,
+            ..GraphPolicy::default()
+        };
+        let (imported, imported_policy) = round_trip(&graph, policy);
+        assert_eq!(imported_policy, policy);
+        assert_eq!(endpoints(&imported), vec![(0, 1), (0, 1), (1, 0)]);
+        assert_eq!(imported.raw_edges()[1].weight, json!({ "w": 2 }));
+        assert_eq!(imported.raw_nodes()[0].weight, json!({ "name": "a" }));
+    }
+
+    #[test]
+    fn can_round_trip_self_loops() {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({}));
+        let b = graph.add_node(json!({}));
+        graph.add_edge(a, a, json!({ "loop": true }));
+        graph.add_edge(b, b, json!({}));
+        graph.add_edge(a, b, json!({}));
+        let policy = GraphPolicy {
+            allow_parallel_edges: false,
+            ..GraphPolicy::default()
+        };
+        let (imported, imported_policy) = round_trip(&graph, policy);
+        assert_eq!(imported_policy, policy);
+        assert_eq!(endpoints(&imported), vec![(0, 0), (1, 1), (0, 1)]);
+        assert_eq!(imported.raw_edges()[0].weight, json!({ "loop": true }));
+    }
+
+    #[test]
+    fn can_import_graphology() {
+        let serialized: SerializedGraph = serde_json::from_value(json!({
+            "attributes": { "name": "ignored" },
+            "options": { "type": "mixed", "multi": false, "allowSelfLoops": true },
+            "nodes": [{ "key": "x" }, { "key": 2, "attributes": { "size": 3 } }],
+            "edges": [
+                { "key": "a", "source": "x", "target": 2 },
+                { "source": 2, "target": "x" }
+            ]
+        }))
+        .unwrap();
+        let (graph, policy) = from_graphology(&serialized, Some("key"), true).unwrap();
+        assert!(!policy.allow_parallel_edges);
+        assert_eq!(graph.raw_nodes()[1].weight, json!({ "key": 2, "size": 3 }));
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "key": "a" }));
+        assert_eq!(graph.raw_edges()[1].weight, json!({}));
+        assert_eq!(endpoints(&graph), vec![(0, 1), (1, 0)]);
+
+        let exported = to_graphology(&graph, policy, Some("key")).unwrap();
+        assert_eq!(exported.edges[0].key, Some(json!("a")));
+        assert_eq!(exported.edges[0].attributes, Some(Map::new()));
+        assert_eq!(exported.edges[1].key, Some(json!("e1")));
+
+        let mut graph = graph;
+        graph[petgraph::graph::EdgeIndex::new(0)] = json!({ "key": "e1" });
+        graph.add_edge(
+            NodeIndex::new(0),
+            NodeIndex::new(0),
+            json!({ "key": "e1_1" }),
+        );
+        graph.add_edge(NodeIndex::new(1), NodeIndex::new(1), json!({}));
+        let exported = to_graphology(&graph, policy, Some("key")).unwrap();
+        let keys: Vec<_> = exported.edges.iter().map(|edge| edge.key.clone()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                Some(json!("e1")),
+                Some(json!("e1_2")),
+                Some(json!("e1_1")),
+                Some(json!("e3"))
+            ]
+        );
+        graph[petgraph::graph::EdgeIndex::new(3)] = json!({ "key": "e1" });
+        assert_eq!(
+            to_graphology(&graph, policy, Some("key")).unwrap_err(),
+            GraphError::new("Duplicate edge key `e1`", GraphItemType::Edge, 3)
+        );
+
+        let mut serialized = serialized;
+        serialized.edges[1].undirected = true;
+        assert_eq!(
+            from_graphology(&serialized, None, true).unwrap_err(),
+            invalid_graph("edge 1 is undirected, use `UnGraph.fromGraphology`")
+        );
+        assert_eq!(
+            from_graphology(&serialized, None, false).unwrap_err(),
+            invalid_graph("edge 0 is directed, use `DiGraph.fromGraphology`")
+        );
+
+        let serialized: SerializedGraph = serde_json::from_value(json!({
+            "options": { "type": "undirected", "multi": false, "allowSelfLoops": true },
+            "nodes": [{ "key": "x" }, { "key": "y" }],
+            "edges": [{ "source": "x", "target": "y" }, { "source": "y", "target": "x" }]
+        }))
+        .unwrap();
+        assert_eq!(
+            from_graphology(&serialized, None, false).unwrap_err(),
+            invalid_graph("edge 1 is a parallel edge but `multi` is false")
+        );
+        assert_eq!(
+            from_graphology(&serialized, None, true).unwrap_err(),
+            invalid_graph("edge 0 is undirected, use `UnGraph.fromGraphology`")
+        );
+    }
+
+    #[test]
+    fn can_reject_invalid_graphology() {
+        let error = |value: Value| {
+            let serialized: SerializedGraph = serde_json::from_value(value).unwrap();
+            from_graphology(&serialized, None, true).unwrap_err()
+        };
+        assert_eq!(
+            error(json!({ "nodes": [{ "key": "a" }, { "key": "a" }] })),
+            invalid_graph("duplicate node key `a`")
+        );
+        assert_eq!(
+            error(
+                json!({ "nodes": [{ "key": "a" }], "edges": [{ "source": "a", "target": "b" }] })
+            ),
+            invalid_graph("edge 0 references unknown node `b`")
+        );
+        assert_eq!(
+            error(json!({
+                "options": { "type": "directed", "multi": true, "allowSelfLoops": false },
+                "nodes": [{ "key": "a" }],
+                "edges": [{ "source": "a", "target": "a" }]
+            })),
+            invalid_graph("edge 0 is a self loop but `allowSelfLoops` is false")
+        );
+        let nodes = json!([{ "key": "a" }]);
+        assert_eq!(
+            error(json!({
+                "nodes": nodes,
+                "edges": [
+                    { "key": 1, "source": "a", "target": "a" },
+                    { "key": "1", "source": "a", "target": "a" }
+                ]
+            })),
+            invalid_graph("duplicate edge key `1`")
+        );
+        assert_eq!(
+            error(json!({
+                "nodes": nodes,
+                "edges": [{ "key": [1], "source": "a", "target": "a" }]
+            })),
+            invalid_graph("edge 0 must have a string or number key")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_graphology() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let data = g.to_graphology(JsValue::UNDEFINED).unwrap();
+        let imported = DiGraph::from_graphology(data, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.edge_endpoints_all(), g.edge_endpoints_all());
+        assert_eq!(imported.policy, g.policy);
+        assert!(UnGraph::from_graphology(
+            g.to_graphology(JsValue::UNDEFINED).unwrap(),
+            JsValue::UNDEFINED
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! JSON serialization of `DiGraph` using the same representation as petgraph's
+//! `serde-1` feature, so graphs can be exchanged with Rust code using petgraph:
+//!
+//! ```json
+//! {
+//!   "nodes": ["NYC", "Vilnius"],
+//!   "node_holes": [],
+//!   "edge_property": "directed",
+//!   "edges": [[1, 0, null]]
+//! }
+//! ```
+//!
+//! Node and edge weights are carried as JSON values.
+
+use crate::graph_impl::DiGraph;
+use crate::{GraphError, GraphItemType};
+use js_sys::{Array, Object};
+use petgraph::graph;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+/// A graph with its weights converted to JSON values.
+pub type JsonGraph = graph::DiGraph<Value, Value>;
+
+/// Parse and validate the text of a JSON graph.
+pub fn parse_json_graph(text: &str) -> Result<JsonGraph, GraphError> {
+    serde_json::from_str(text)
+        .map_err(|err| GraphError::from_message(&format!("Invalid graph JSON: {}", err)))
+}
+
+/// Convert a JSON value to the Javascript value `JSON.parse` gives for it,
+/// without going through its text.
+pub fn json_to_js(value: Value) -> JsValue {
+    match value {
+        Value::Null => JsValue::NULL,
+        Value::Bool(value) => JsValue::from_bool(value),
+        Value::Number(number) => JsValue::from_f64(number.as_f64().unwrap_or(f64::NAN)),
+        Value::String(text) => JsValue::from_str(&text),
+        Value::Array(values) => values.into_iter().map(json_to_js).collect::<Array>().into(),
+        // `Object.fromEntries` defines own properties like `JSON.parse`, also
+        // for a `__proto__` key.
+        Value::Object(map) => {
+            let entries: Array = map
+                .into_iter()
+                .map(|(key, value)| Array::of2(&JsValue::from_str(&key), &json_to_js(value)))
+                .collect();
+            Object::from_entries(&entries)
+                .expect("entries are key value pairs")
+                .into()
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Get the graph as a plain JSON object, which is also used by
+    /// `JSON.stringify`.
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        let json_graph = self.to_json_graph().map_err(|err| err.to_js_value())?;
+        JsValue::from_serde(&json_graph).map_err(|_| JsValue::from_str("Failed to transform graph"))
+    }
+
+    /// Create a graph from the result of `toJSON`, either as an object or as
+    /// a JSON string. Malformed input throws a `GraphError`.
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(json: JsValue) -> Result<DiGraph, JsValue> {
+        let json_graph = match json.as_string() {
+            Some(text) => parse_json_graph(&text),
+            None => json
+                .into_serde()
+                .map_err(|err| GraphError::from_message(&format!("Invalid graph JSON: {}", err))),
+        }
+        .map_err(|err| err.to_js_value())?;
+        DiGraph::from_json_graph(&json_graph).map_err(|err| err.to_js_value())
+    }
+}
+
+impl DiGraph {
+    /// Copy the graph with all weights converted to JSON values, keeping all
+    /// indices.
+    pub fn to_json_graph(&self) -> Result<JsonGraph, GraphError> {
+        let mut json_graph =
+            JsonGraph::with_capacity(self.graph.node_count(), self.graph.edge_count());
+        for (index, node) in self.graph.raw_nodes().iter().enumerate() {
+            let weight = node.weight.into_serde().map_err(|_| {
+                GraphError::new(
+                    "Failed to serialize node weight",
+                    GraphItemType::Node,
+                    index as u32,
+                )
+            })?;
+            json_graph.add_node(weight);
+        }
+        for (index, edge) in self.graph.raw_edges().iter().enumerate() {
+            let weight = edge.weight.into_serde().map_err(|_| {
+                GraphError::new(
+                    "Failed to serialize edge weight",
+                    GraphItemType::Edge,
+                    index as u32,
+                )
+            })?;
+            json_graph.add_edge(edge.source(), edge.target(), weight);
+        }
+        Ok(json_graph)
+    }
+
+    /// Create a graph from one with JSON value weights, keeping all indices.
+    pub fn from_json_graph(json_graph: &JsonGraph) -> Result<DiGraph, GraphError> {
+        let mut graph =
+            graph::DiGraph::with_capacity(json_graph.node_count(), json_graph.edge_count());
+        for (index, node) in json_graph.raw_nodes().iter().enumerate() {
+            let weight = JsValue::from_serde(&node.weight).map_err(|_| {
+                GraphError::new(
+                    "Failed to deserialize node weight",
+                    GraphItemType::Node,
+                    index as u32,
+                )
+            })?;
+            graph.add_node(weight);
+        }
+        for (index, edge) in json_graph.raw_edges().iter().enumerate() {
+            let weight = JsValue::from_serde(&edge.weight).map_err(|_| {
+                GraphError::new(
+                    "Failed to deserialize edge weight",
+                    GraphItemType::Edge,
+                    index as u32,
+                )
+            })?;
+            graph.add_edge(edge.source(), edge.target(), weight);
+        }
+        Ok(DiGraph::from(graph))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    #[test]
+    fn can_parse_json_graph() {
+        let text = json!({
+            "nodes": ["NYC", {"name": "Vilnius"}, 3],
+            "node_holes": [],
+            "edge_property": "directed",
+            "edges": [[1, 0, null], [2, 1, {"km": 7000}]],
+        })
+        .to_string();
+        let json_graph = parse_json_graph(&text).unwrap();
+        assert_eq!(json_graph.node_count(), 3);
+        assert_eq!(
+            json_graph[graph::NodeIndex::new(1)],
+            json!({"name": "Vilnius"})
+        );
+        assert_eq!(
+            json_graph.edge_endpoints(graph::EdgeIndex::new(1)),
+            Some((graph::NodeIndex::new(2), graph::NodeIndex::new(1)))
+        );
+        assert_eq!(json_graph[graph::EdgeIndex::new(1)], json!({"km": 7000}));
+    }
+
+    #[test]
+    fn can_reject_malformed_json_graph() {
+        let malformed = vec![
+            json!({"nodes": [1], "edge_property": "directed", "edges": [[0, 1, null]]}),
+            json!({"nodes": [1], "edge_property": "undirected", "edges": []}),
+            json!({"nodes": [1], "node_holes": [0], "edge_property": "directed", "edges": []}),
+            json!({"nodes": [1], "edge_property": "directed", "edges": [[0, 0]]}),
+            json!({"nodes": [1], "edges": []}),
+            json!([1, 2]),
+        ];
+        for value in malformed {
+            assert!(parse_json_graph(&value.to_string()).is_err(), "{}", value);
+        }
+        assert!(parse_json_graph("{\"nodes\": [").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_json() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let json = g.to_json().unwrap();
+        let g2 = DiGraph::from_json(json).unwrap();
+        assert_eq!(g2.node_weights(), g.node_weights());
+        assert_eq!(g2.edge_endpoints_all(), g.edge_endpoints_all());
+        assert_eq!(g2.edge_weight(3).unwrap(), JsValue::NULL);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_convert_json_to_js() {
+        let value = json!({"__proto__": [1, 2.5, true, null], "name": "Vilnius"});
+        let js = json_to_js(value.clone());
+        assert_eq!(js.into_serde::<Value>().unwrap(), value);
+        assert_eq!(json_to_js(json!("NYC")), "NYC");
+    }
+
+    #[wasm_bindgen_test]
+    fn can_load_json_string() {
+        let text = "{\"nodes\":[\"a\",\"b\"],\"node_holes\":[],\"edge_property\":\"directed\",\
+                    \"edges\":[[0,1,2.5]]}";
+        let g = DiGraph::from_json(JsValue::from_str(text)).unwrap();
+        assert_eq!(g.edge_weight(0).unwrap(), 2.5);
+        let err: GraphError = DiGraph::from_json(JsValue::from_str("{}"))
+            .err()
+            .unwrap()
+            .into_serde()
+            .unwrap();
+        assert_eq!(
+            err,
+            GraphError::from_message(
+                "Invalid graph JSON: missing field `nodes` at line 1 column 2"
+            )
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! `GraphLoader`, which builds a graph from a file given in byte chunks, so
+//! large files can be streamed into WASM without holding the whole text in
+//! Javascript or adding every edge with a call across the boundary.
+//!
+//! The loader reads delimited edge lists with the parser of `csv` and JSON
+//! lines, where every line is a node or edge object.
+
+use crate::formats::csv::{EdgeListOptions, EdgeListParser, KeyedNodes};
+use crate::formats::id_text;
+use crate::formats::json::json_to_js;
+use crate::formats::node_link::NodeLinkFields;
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::get_string_option;
+use crate::GraphError;
+use petgraph::graph::{self, NodeIndex};
+use serde_json::Value;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// Decodes UTF-8 text given in byte chunks, which may split characters.
+#[derive(Debug, Clone, Default)]
+pub struct Utf8Decoder {
+    /// Bytes of a character split by the end of the last chunk.
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    /// Decode a chunk, keeping a character it ends in the middle of for the
+    /// next chunk.
+    pub fn decode(&mut self, chunk: &[u8]) -> Result<String, GraphError> {
+        self.pending.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(GraphError::from_message("Invalid UTF-8 in input")),
+        };
+        let rest = self.pending.split_off(valid);
+        let text = std::mem::replace(&mut self.pending, rest);
+        Ok(String::from_utf8(text).expect("bytes were validated"))
+    }
+
+    /// Check that the input didn't end in the middle of a character.
+    pub fn finish(&self) -> Result<(), GraphError> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(GraphError::from_message(
+                "Input ends with a truncated UTF-8 character",
+            ))
+        }
+    }
+}
+
+fn invalid_line(message: &str, line: u32, column: usize) -> GraphError {
+    GraphError::from_message(&format!("Invalid JSON lines: {}", message))
+        .with_location(line, column as u32)
+}
+
+/// Incremental parser of JSON lines, where every line is an edge object with
+/// `source` and `target` node keys or a node object with an `id` key, using
+/// the field names of `NodeLinkFields`. Nodes are created on first use of
+/// their key with the key as weight, which a node line replaces with its
+/// object. Weights are the objects without their key fields, or the value of
+/// the weight field if there is one, converted by `weight` as the lines are
+/// parsed.
+pub struct JsonLinesParser<W> {
+    fields: NodeLinkFields,
+    line: String,
+    line_number: u32,
+    graph: graph::DiGraph<W, W>,
+    nodes: KeyedNodes,
+    declared: HashSet<NodeIndex>,
+    weight: fn(Value) -> W,
+}
+
+impl<W> JsonLinesParser<W> {
+    pub fn new(fields: NodeLinkFields, weight: fn(Value) -> W) -> Self {
+        JsonLinesParser {
+            fields,
+            line: String::new(),
+            line_number: 0,
+            graph: graph::DiGraph::new(),
+            nodes: KeyedNodes::default(),
+            declared: HashSet::new(),
+            weight,
+        }
+    }
+
+    fn parse_line(&mut self, text: &str) -> Result<(), GraphError> {
+        self.line_number += 1;
+        let line = self.line_number;
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+        let value: Value = serde_json::from_str(text).map_err(|err| {
+            // Drop serde's position, which is replaced by the location.
+            let message = err.to_string();
+            let message = message.split(" at line ").next().unwrap_or_default();
+            invalid_line(message, line, err.column())
+        })?;
+        let object = value
+            .as_object()
+            .ok_or_else(|| invalid_line("expected an object", line, 1))?;
+        let fields = &self.fields;
+        let key = |field: &str| {
+            id_text(object.get(field)).ok_or_else(|| {
+                let message = format!("`{}` must be a string or number", field);
+                invalid_line(&message, line, 1)
+            })
+        };
+        if object.contains_key(&fields.source) || object.contains_key(&fields.target) {
+            let (source, target) = (key(&fields.source)?, key(&fields.target)?);
+            let weight = fields.weight(object, &[&fields.source, &fields.target]);
+            let source = self.nodes.node(&mut self.graph, &source, self.weight);
+            let target = self.nodes.node(&mut self.graph, &target, self.weight);
+            self.graph.add_edge(source, target, (self.weight)(weight));
+        } else if object.contains_key(&fields.id) {
+            let id = key(&fields.id)?;
+            let weight = fields.weight(object, &[&fields.id]);
+            let node = self.nodes.node(&mut self.graph, &id, self.weight);
+            if !self.declared.insert(node) {
+                return Err(invalid_line(&format!("duplicate node `{}`", id), line, 1));
+            }
+            self.graph[node] = (self.weight)(weight);
+        } else {
+            let message = format!(
+                "expected a node with `{}` or an edge with `{}` and `{}`",
+                fields.id, fields.source, fields.target
+            );
+            return Err(invalid_line(&message, line, 1));
+        }
+        Ok(())
+    }
+
+    /// Parse a chunk of text.
+    pub fn push(&mut self, chunk: &str) -> Result<(), GraphError> {
+        let mut rest = chunk;
+        while let Some(end) = rest.find('\n') {
+            let mut line = std::mem::take(&mut self.line);
+            line.push_str(&rest[..end]);
+            self.parse_line(&line)?;
+            // Keep the allocation for the next line.
+            line.clear();
+            self.line = line;
+            rest = &rest[end + 1..];
+        }
+        self.line.push_str(rest);
+        Ok(())
+    }
+
+    /// Parse the rest of the text and get the graph.
+    pub fn finish(mut self) -> Result<graph::DiGraph<W, W>, GraphError> {
+        let line = std::mem::take(&mut self.line);
+        self.parse_line(&line)?;
+        Ok(self.graph)
+    }
+}
+
+enum LoaderParser<W> {
+    EdgeList(EdgeListParser<W>),
+    JsonLines(JsonLinesParser<W>),
+}
+
+impl<W> LoaderParser<W> {
+    fn push(&mut self, text: &str) -> Result<(), GraphError> {
+        match self {
+            LoaderParser::EdgeList(parser) => parser.push(text),
+            LoaderParser::JsonLines(parser) => parser.push(text),
+        }
+    }
+
+    fn finish(self) -> Result<graph::DiGraph<W, W>, GraphError> {
+        match self {
+            LoaderParser::EdgeList(parser) => parser.finish(),
+            LoaderParser::JsonLines(parser) => parser.finish(),
+        }
+    }
+}
+
+/// Incremental graph loader, which is given a file in `Uint8Array` chunks
+/// such as those of a Node.js read stream or a `fetch` body and parses them
+/// as they arrive:
+///
+/// ```js
+/// const loader = new GraphLoader({ format: "jsonl" })
+/// for await (const chunk of fs.createReadStream("graph.jsonl")) {
+///     loader.push(chunk)
+/// }
+/// const graph = loader.finish()
+/// ```
+///
+/// A loader which threw an error should be discarded.
+#[wasm_bindgen]
+pub struct GraphLoader {
+    decoder: Utf8Decoder,
+    parser: LoaderParser<JsValue>,
+}
+
+#[wasm_bindgen]
+impl GraphLoader {
+    /// Create a loader. Options:
+    ///
+    /// * `format` - `"edgelist"` (the default) for delimited edge lists read
+    ///   like `DiGraph.fromEdgeList`, taking all of its options, or `"jsonl"`
+    ///   for JSON lines.
+    ///
+    /// Every line of JSON lines is an edge object such as
+    /// `{ "source": "a", "target": "b", "cost": 2 }` or a node object such as
+    /// `{ "id": "a", "name": "Alice" }`, which may come before or after the
+    /// node's edges. Nodes are created for every key with the key as weight,
+    /// node lines replace it with the object without its `id`, edge weights
+    /// are the object without `source` and `target`. The `idField`,
+    /// `sourceField`, `targetField` and `weightField` options of
+    /// `DiGraph.fromNodeLink` change the field names.
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<GraphLoader, JsValue> {
+        let parser = match get_string_option(&options, "format")?.as_deref() {
+            None | Some("edgelist") => LoaderParser::EdgeList(EdgeListParser::new(
+                EdgeListOptions::from_options(&options)?,
+                json_to_js,
+            )),
+            Some("jsonl") => LoaderParser::JsonLines(JsonLinesParser::new(
+                NodeLinkFields::from_options(&options)?,
+                json_to_js,
+            )),
+            Some(_) => {
+                return Err(GraphError::from_message(
+                    "Option `format` must be \"edgelist\" or \"jsonl\"",
+                )
+                .to_js_value())
+            }
+        };
+        Ok(GraphLoader {
+            decoder: Utf8Decoder::default(),
+            parser,
+        })
+    }
+
+    /// Parse the next chunk of UTF-8 text. Errors are thrown as a
+    /// `GraphError` with the `location` of the row or line.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), JsValue> {
+        self.decoder
+            .decode(chunk)
+            .and_then(|text| self.parser.push(&text))
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Parse the rest of the text and get the graph.
+    pub fn finish(self) -> Result<DiGraph, JsValue> {
+        let parser = self.parser;
+        self.decoder
+            .finish()
+            .and_then(|_| parser.finish())
+            .map(DiGraph::from)
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::json::JsonGraph;
+    use serde_json::json;
+    use std::convert::identity;
+    use wasm_bindgen_test::*;
+
+    fn endpoints(graph: &JsonGraph) -> Vec<(usize, usize)> {
+        graph
+            .raw_edges()
+            .iter()
+            .map(|e| (e.source().index(), e.target().index()))
+            .collect()
+    }
+
+    fn load(
+        mut parser: LoaderParser<Value>,
+        text: &str,
+        chunk_size: usize,
+    ) -> Result<JsonGraph, GraphError> {
+        let mut decoder = Utf8Decoder::default();
+        for chunk in text.as_bytes().chunks(chunk_size) {
+            parser.push(&decoder.decode(chunk)?)?;
+        }
+        decoder.finish()?;
+        parser.finish()
+    }
+
+    #[test]
+    fn can_decode_split_characters() {
+        let text = "zürich → 東京";
+        let mut decoder = Utf8Decoder::default();
+        let decoded: String = text
+            .as_bytes()
+            .chunks(1)
+            .map(|chunk| decoder.decode(chunk).unwrap())
+            .collect();
+        assert_eq!(decoded, text);
+        assert!(decoder.finish().is_ok());
+
+        let mut decoder = Utf8Decoder::default();
+        assert_eq!(decoder.decode(&text.as_bytes()[..2]).unwrap(), "z");
+        assert!(decoder.finish().is_err());
+        assert!(Utf8Decoder::default().decode(&[b'a', 0xff, b'b']).is_err());
+    }
+
+    #[test]
+    fn can_parse_json_lines() {
+        let text = "{\"source\": \"a\", \"target\": 2, \"cost\": 1.5}\n\
+                    \n\
+                    {\"id\": \"a\", \"name\": \"Alice\"}\r\n\
+                    {\"source\": 2, \"target\": \"c\"}";
+        let parser = JsonLinesParser::new(NodeLinkFields::default(), identity);
+        let graph = load(LoaderParser::JsonLines(parser), text, 5).unwrap();
+        let weights: Vec<&Value> = graph.raw_nodes().iter().map(|n| &n.weight).collect();
+        assert_eq!(
+            weights,
+            vec![&json!({ "name": "Alice" }), &json!("2"), &json!("c")]
+        );
+        assert_eq!(endpoints(&graph), vec![(0, 1), (1, 2)]);
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "cost": 1.5 }));
+        assert_eq!(graph.raw_edges()[1].weight, json!({}));
+    }
+
+    #[test]
+    fn can_report_json_lines_errors() {
+        let error = |text: &str| {
+            let mut parser = JsonLinesParser::new(NodeLinkFields::default(), identity);
+            parser.push(text).and_then(|_| parser.finish()).unwrap_err()
+        };
+        assert_eq!(
+            error("{\"id\": 1}\n{\"id\": 1,}"),
+            invalid_line("trailing comma", 2, 10)
+        );
+        assert_eq!(
+            error("{\"id\": 1}\n{\"id\": 1}\n"),
+            invalid_line("duplicate node `1`", 2, 1)
+        );
+        assert_eq!(
+            error("{\"source\": 1}"),
+            invalid_line("`target` must be a string or number", 1, 1)
+        );
+        assert_eq!(error("[1, 2]"), invalid_line("expected an object", 1, 1));
+    }
+
+    #[test]
+    fn can_load_edge_lists() {
+        let parser = EdgeListParser::new(EdgeListOptions::default(), identity);
+        let text = "source,target\nzürich,東京\n東京,zürich\n";
+        let graph = load(LoaderParser::EdgeList(parser), text, 3).unwrap();
+        let weights: Vec<&Value> = graph.raw_nodes().iter().map(|n| &n.weight).collect();
+        assert_eq!(weights, vec!["zürich", "東京"]);
+        assert_eq!(endpoints(&graph), vec![(0, 1), (1, 0)]);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_load_graphs_in_chunks() {
+        let mut loader = GraphLoader::new(JsValue::UNDEFINED).unwrap();
+        loader.push(b"source,target\na,").unwrap();
+        loader.push(b"b\nb,c").unwrap();
+        let g = loader.finish().unwrap();
+        assert_eq!(g.node_count(), 3);
+        assert_eq!(g.edge_endpoints(1), Some(vec![1, 2]));
+    }
+}
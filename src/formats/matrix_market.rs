@@ -0,0 +1,542 @@
+//! Matrix Market (`.mtx`) reader for sparse matrices in coordinate format, as
+//! used by the SuiteSparse and SNAP datasets. Rows and columns are nodes and
+//! every entry is an edge from its row to its column.
+//!
+//! The parser takes the file in byte chunks of any size and only keeps the
+//! current line and the graph built so far, so large files can be streamed
+//! from disk.
+
+use crate::formats::MAX_NODE_COUNT;
+use crate::graph_impl::{DiGraph, UnGraph};
+use crate::GraphError;
+use petgraph::graph::{self, EdgeIndex, NodeIndex};
+use wasm_bindgen::prelude::*;
+
+/// Entries reserved up front at most, so a bogus size line can't allocate
+/// arbitrary memory.
+const MAX_RESERVED_ENTRIES: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixField {
+    Pattern,
+    Real,
+    Integer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixSymmetry {
+    General,
+    /// Only the lower triangle is stored and every off-diagonal entry stands
+    /// for its mirror image too.
+    Symmetric,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixMarketHeader {
+    pub field: MatrixField,
+    pub symmetry: MatrixSymmetry,
+    pub rows: usize,
+    pub columns: usize,
+    pub entries: usize,
+}
+
+/// A parsed coordinate matrix as a graph with a node for every row and
+/// column and an edge from row to column for every entry, in file order.
+#[derive(Debug, Clone)]
+pub struct MatrixMarket<W> {
+    pub header: MatrixMarketHeader,
+    pub graph: graph::DiGraph<W, W>,
+}
+
+/// Weight of a value for graphs with JS weights, `null` for nodes and the
+/// entries of pattern matrices.
+fn js_weight(value: Option<f64>) -> JsValue {
+    value.map_or(JsValue::NULL, JsValue::from_f64)
+}
+
+fn invalid_mtx(message: &str, line: u32, column: usize) -> GraphError {
+    GraphError::from_message(&format!("Invalid Matrix Market: {}", message))
+        .with_location(line, column as u32)
+}
+
+/// Incremental Matrix Market parser, which adds the nodes once the size line
+/// is read and an edge as each entry is read. Weights are created by
+/// `weight` from the entry's value, or `None` for nodes and the entries of
+/// pattern matrices.
+#[derive(Debug, Clone)]
+pub struct MatrixMarketParser<W> {
+    /// Bytes of the current line read so far.
+    line: Vec<u8>,
+    line_number: u32,
+    banner: Option<(MatrixField, MatrixSymmetry)>,
+    header: Option<MatrixMarketHeader>,
+    graph: graph::DiGraph<W, W>,
+    weight: fn(Option<f64>) -> W,
+}
+
+impl<W> MatrixMarketParser<W> {
+    pub fn new(weight: fn(Option<f64>) -> W) -> Self {
+        MatrixMarketParser {
+            line: Vec::new(),
+            line_number: 0,
+            banner: None,
+            header: None,
+            graph: graph::DiGraph::default(),
+            weight,
+        }
+    }
+
+    fn parse_banner(&self, text: &str) -> Result<(MatrixField, MatrixSymmetry), GraphError> {
+        let line = self.line_number;
+        let lower = text.to_ascii_lowercase();
+        let mut words = lower.split_whitespace();
+        if words.next() != Some("%%matrixmarket") {
+            return Err(invalid_mtx("expected a `%%MatrixMarket` banner", line, 1));
+        }
+        let words: Vec<&str> = words.collect();
+        let unsupported = |index: usize, expected: &str| {
+            let found = words.get(index).copied().unwrap_or("nothing");
+            invalid_mtx(
+                &format!("expected {}, found `{}`", expected, found),
+                line,
+                index + 2,
+            )
+        };
+        if words.first() != Some(&"matrix") {
+            return Err(unsupported(0, "`matrix`"));
+        }
+        if words.get(1) != Some(&"coordinate") {
+            return Err(unsupported(1, "the `coordinate` format"));
+        }
+        let field = match words.get(2) {
+            Some(&"pattern") => MatrixField::Pattern,
+            Some(&"real") => MatrixField::Real,
+            Some(&"integer") => MatrixField::Integer,
+            _ => return Err(unsupported(2, "a `pattern`, `real` or `integer` field")),
+        };
+        let symmetry = match words.get(3) {
+            Some(&"general") => MatrixSymmetry::General,
+            Some(&"symmetric") => MatrixSymmetry::Symmetric,
+            _ => return Err(unsupported(3, "`general` or `symmetric` symmetry")),
+        };
+        Ok((field, symmetry))
+    }
+
+    fn parse_size(
+        &self,
+        text: &str,
+        (field, symmetry): (MatrixField, MatrixSymmetry),
+    ) -> Result<MatrixMarketHeader, GraphError> {
+        let sizes: Vec<&str> = text.split_whitespace().collect();
+        if sizes.len() != 3 {
+            return Err(invalid_mtx(
+                "expected a size line of rows, columns and entries",
+                self.line_number,
+                1,
+            ));
+        }
+        let mut numbers = [0; 3];
+        for (index, size) in sizes.iter().enumerate() {
+            numbers[index] = size.parse::<u32>().map_err(|_| {
+                let message = format!("expected a size, found `{}`", size);
+                invalid_mtx(&message, self.line_number, index + 1)
+            })? as usize;
+        }
+        if symmetry == MatrixSymmetry::Symmetric && numbers[0] != numbers[1] {
+            let message = format!(
+                "symmetric matrices must be square, found {} rows and {} columns",
+                numbers[0], numbers[1]
+            );
+            return Err(invalid_mtx(&message, self.line_number, 2));
+        }
+        let node_count = numbers[0].max(numbers[1]);
+        if node_count > MAX_NODE_COUNT {
+            let message = format!("too many nodes ({})", node_count);
+            return Err(invalid_mtx(&message, self.line_number, 1));
+        }
+        Ok(MatrixMarketHeader {
+            field,
+            symmetry,
+            rows: numbers[0],
+            columns: numbers[1],
+            entries: numbers[2],
+        })
+    }
+
+    fn parse_entry(&mut self, text: &str) -> Result<(), GraphError> {
+        let header = self.header.as_ref().expect("size line was read");
+        let line = self.line_number;
+        if self.graph.edge_count() == header.entries {
+            let message = format!("more than the {} entries of the size line", header.entries);
+            return Err(invalid_mtx(&message, line, 1));
+        }
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let expected = if header.field == MatrixField::Pattern {
+            2
+        } else {
+            3
+        };
+        if fields.len() != expected {
+            let message = format!("expected {} values, found {}", expected, fields.len());
+            return Err(invalid_mtx(&message, line, 1));
+        }
+        let index = |column: usize, size: usize| {
+            fields[column]
+                .parse::<u32>()
+                .ok()
+                .filter(|&index| index >= 1 && index as usize <= size)
+                .map(|index| index - 1)
+                .ok_or_else(|| {
+                    let message = format!("expected an index from 1 to {}", size);
+                    invalid_mtx(&message, line, column + 1)
+                })
+        };
+        let row = index(0, header.rows)?;
+        let column = index(1, header.columns)?;
+        let number = match header.field {
+            MatrixField::Pattern => None,
+            MatrixField::Real => Some(fields[2].parse::<f64>().ok()),
+            MatrixField::Integer => Some(fields[2].parse::<i64>().ok().map(|value| value as f64)),
+        };
+        let value = match number {
+            None => None,
+            Some(Some(value)) => Some(value),
+            Some(None) => {
+                let message = format!("expected a number, found `{}`", fields[2]);
+                return Err(invalid_mtx(&message, line, 3));
+            }
+        };
+        let weight = (self.weight)(value);
+        self.graph.add_edge(
+            NodeIndex::new(row as usize),
+            NodeIndex::new(column as usize),
+            weight,
+        );
+        Ok(())
+    }
+
+    fn parse_line(&mut self, bytes: &[u8]) -> Result<(), GraphError> {
+        self.line_number += 1;
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| invalid_mtx("invalid UTF-8", self.line_number, 1))?
+            .trim();
+        let banner = match self.banner {
+            None => {
+                self.banner = Some(self.parse_banner(text)?);
+                return Ok(());
+            }
+            Some(banner) => banner,
+        };
+        if text.is_empty() || text.starts_with('%') {
+            return Ok(());
+        }
+        if self.header.is_none() {
+            let header = self.parse_size(text, banner)?;
+            let node_count = header.rows.max(header.columns);
+            self.graph.reserve_nodes(node_count);
+            self.graph
+                .reserve_edges(header.entries.min(MAX_RESERVED_ENTRIES));
+            for _ in 0..node_count {
+                self.graph.add_node((self.weight)(None));
+            }
+            self.header = Some(header);
+            return Ok(());
+        }
+        self.parse_entry(text)
+    }
+
+    /// Parse a chunk of the file.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), GraphError> {
+        let mut rest = chunk;
+        while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+            let mut line = std::mem::take(&mut self.line);
+            line.extend_from_slice(&rest[..end]);
+            self.parse_line(&line)?;
+            // Keep the allocation for the next line.
+            line.clear();
+            self.line = line;
+            rest = &rest[end + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        Ok(())
+    }
+
+    /// Parse the rest of the file and get the matrix.
+    pub fn finish(mut self) -> Result<MatrixMarket<W>, GraphError> {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.parse_line(&line)?;
+        }
+        let line = self.line_number.max(1);
+        if self.banner.is_none() {
+            return Err(invalid_mtx("expected a `%%MatrixMarket` banner", line, 1));
+        }
+        let header = self
+            .header
+            .ok_or_else(|| invalid_mtx("missing the size line", line, 1))?;
+        if self.graph.edge_count() != header.entries {
+            let message = format!(
+                "expected {} entries, found {}",
+                header.entries,
+                self.graph.edge_count()
+            );
+            return Err(invalid_mtx(&message, line, 1));
+        }
+        Ok(MatrixMarket {
+            header,
+            graph: self.graph,
+        })
+    }
+}
+
+/// Parse a whole Matrix Market file.
+pub fn parse_matrix_market<W>(
+    bytes: &[u8],
+    weight: fn(Option<f64>) -> W,
+) -> Result<MatrixMarket<W>, GraphError> {
+    let mut parser = MatrixMarketParser::new(weight);
+    parser.push(bytes)?;
+    parser.finish()
+}
+
+impl<W> MatrixMarket<W> {
+    /// Number of nodes, which is the larger of the row and column counts.
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+}
+
+impl MatrixMarket<JsValue> {
+    /// Get the directed graph, where the entries of symmetric matrices also
+    /// add their mirror image edge unless they are on the diagonal. Mirror
+    /// edges come after the edges of all entries.
+    pub fn into_digraph(self) -> DiGraph {
+        let mut graph = self.graph;
+        if self.header.symmetry == MatrixSymmetry::Symmetric {
+            let entries = graph.edge_count();
+            graph.reserve_edges(entries);
+            for index in 0..entries {
+                let edge = EdgeIndex::new(index);
+                let (row, column) = graph.edge_endpoints(edge).expect("entry edge exists");
+                if row != column {
+                    let weight = graph[edge].clone();
+                    graph.add_edge(column, row, weight);
+                }
+            }
+        }
+        DiGraph::from(graph)
+    }
+
+    /// Get the undirected graph with an edge for every entry.
+    pub fn into_ungraph(self) -> UnGraph {
+        UnGraph::from(self.graph.into_edge_type())
+    }
+}
+
+/// Streaming Matrix Market reader, which is given the file in chunks such as
+/// the `Uint8Array`s of a Node.js read stream or a `fetch` body:
+///
+/// ```js
+/// const reader = new MatrixMarketReader()
+/// for await (const chunk of fs.createReadStream("graph.mtx")) {
+///     reader.push(chunk)
+/// }
+/// const graph = reader.finish()
+/// ```
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct MatrixMarketReader {
+    parser: MatrixMarketParser<JsValue>,
+}
+
+impl Default for MatrixMarketReader {
+    fn default() -> Self {
+        MatrixMarketReader {
+            parser: MatrixMarketParser::new(js_weight),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MatrixMarketReader {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        MatrixMarketReader::default()
+    }
+
+    /// Parse the next chunk of the file. Errors are thrown as a `GraphError`
+    /// with the `location` of the line and value.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), JsValue> {
+        self.parser.push(chunk).map_err(|err| err.to_js_value())
+    }
+
+    /// Parse the rest of the file and get a `DiGraph`, see
+    /// `DiGraph.fromMatrixMarket`.
+    pub fn finish(self) -> Result<DiGraph, JsValue> {
+        self.parser
+            .finish()
+            .map(MatrixMarket::into_digraph)
+            .map_err(|err| err.to_js_value())
+    }
+
+    /// Parse the rest of the file and get an `UnGraph`, see
+    /// `UnGraph.fromMatrixMarket`.
+    #[wasm_bindgen(js_name = finishUndirected)]
+    pub fn finish_undirected(self) -> Result<UnGraph, JsValue> {
+        self.parser
+            .finish()
+            .map(MatrixMarket::into_ungraph)
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Create a graph from a Matrix Market coordinate matrix of `pattern`,
+    /// `real` or `integer` values with `general` or `symmetric` symmetry.
+    /// There is a node with a `null` weight for every row and column and an
+    /// edge from row to column for every entry, with the value as weight, or
+    /// `null` for pattern matrices. Entries of symmetric matrices add edges in
+    /// both directions, with the mirror image edges after all entries, and
+    /// must be square. Matrices may have at most 16777216 rows and columns.
+    ///
+    /// Errors have a `location` with the line and the number of the value.
+    /// Use `MatrixMarketReader` to read large files in chunks.
+    #[wasm_bindgen(js_name = fromMatrixMarket)]
+    pub fn from_matrix_market(text: &str) -> Result<DiGraph, JsValue> {
+        parse_matrix_market(text.as_bytes(), js_weight)
+            .map(MatrixMarket::into_digraph)
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[wasm_bindgen]
+impl UnGraph {
+    /// Create an undirected graph from a Matrix Market file with an edge for
+    /// every entry, see `DiGraph.fromMatrixMarket`.
+    #[wasm_bindgen(js_name = fromMatrixMarket)]
+    pub fn from_matrix_market(text: &str) -> Result<UnGraph, JsValue> {
+        parse_matrix_market(text.as_bytes(), js_weight)
+            .map(MatrixMarket::into_ungraph)
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    const SYMMETRIC: &str = "%%MatrixMarket matrix coordinate integer symmetric\n\
+                             % comment\n\
+                             \n\
+                             3 3 3\n\
+                             1 1 5\n\
+                             3 1 -2\n\
+                             3 2 7\n";
+
+    fn entries(matrix: &MatrixMarket<Option<f64>>) -> Vec<(usize, usize, Option<f64>)> {
+        matrix
+            .graph
+            .raw_edges()
+            .iter()
+            .map(|e| (e.source().index(), e.target().index(), e.weight))
+            .collect()
+    }
+
+    #[test]
+    fn can_parse_matrix_market_in_chunks() {
+        let mut parser = MatrixMarketParser::new(|value| value);
+        for chunk in SYMMETRIC.as_bytes().chunks(7) {
+            parser.push(chunk).unwrap();
+        }
+        let matrix = parser.finish().unwrap();
+        assert_eq!(
+            matrix.header,
+            MatrixMarketHeader {
+                field: MatrixField::Integer,
+                symmetry: MatrixSymmetry::Symmetric,
+                rows: 3,
+                columns: 3,
+                entries: 3,
+            }
+        );
+        assert_eq!(matrix.node_count(), 3);
+        assert_eq!(
+            entries(&matrix),
+            vec![(0, 0, Some(5.0)), (2, 0, Some(-2.0)), (2, 1, Some(7.0))]
+        );
+    }
+
+    #[test]
+    fn can_parse_pattern_matrices() {
+        let text = "%%matrixmarket MATRIX Coordinate Pattern General\r\n2 4 2\r\n1 4\r\n2 1";
+        let matrix = parse_matrix_market(text.as_bytes(), |value| value).unwrap();
+        assert_eq!(matrix.node_count(), 4);
+        assert_eq!(entries(&matrix), vec![(0, 3, None), (1, 0, None)]);
+    }
+
+    #[test]
+    fn can_report_invalid_matrix_market() {
+        let error = |text: &str| parse_matrix_market(text.as_bytes(), |value| value).unwrap_err();
+        assert_eq!(
+            error("%%MatrixMarket matrix array real general\n"),
+            invalid_mtx("expected the `coordinate` format, found `array`", 1, 3)
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate complex general\n"),
+            invalid_mtx(
+                "expected a `pattern`, `real` or `integer` field, found `complex`",
+                1,
+                4
+            )
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate real general\n2 2 2\n1 2 1.5\n3 1 1\n"),
+            invalid_mtx("expected an index from 1 to 2", 4, 1)
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate real general\n2 2 2\n1 2 x\n"),
+            invalid_mtx("expected a number, found `x`", 3, 3)
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate pattern general\n2 2 2\n1 2\n"),
+            invalid_mtx("expected 2 entries, found 1", 3, 1)
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate pattern general\n2 2 1\n1 2\n2 1\n"),
+            invalid_mtx("more than the 1 entries of the size line", 4, 1)
+        );
+        assert_eq!(
+            error(""),
+            invalid_mtx("expected a `%%MatrixMarket` banner", 1, 1)
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate pattern general\n4294967295 4294967295 0\n"),
+            invalid_mtx("too many nodes (4294967295)", 2, 1)
+        );
+        assert_eq!(
+            error("%%MatrixMarket matrix coordinate pattern symmetric\n3 2 1\n2 1\n"),
+            invalid_mtx(
+                "symmetric matrices must be square, found 3 rows and 2 columns",
+                2,
+                2
+            )
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_build_graphs_from_matrix_market() {
+        let g = DiGraph::from_matrix_market(SYMMETRIC).unwrap();
+        assert_eq!(g.node_count(), 3);
+        assert_eq!(g.edge_count(), 5);
+        assert_eq!(g.edge_endpoints(3), Some(vec![0, 2]));
+        assert_eq!(g.edge_weight(3).unwrap(), JsValue::from_f64(-2.0));
+        let g = UnGraph::from_matrix_market(SYMMETRIC).unwrap();
+        assert_eq!(g.edge_count(), 3);
+        let mut reader = MatrixMarketReader::new();
+        reader.push(&SYMMETRIC.as_bytes()[..60]).unwrap();
+        reader.push(&SYMMETRIC.as_bytes()[60..]).unwrap();
+        assert_eq!(reader.finish().unwrap().edge_count(), 5);
+    }
+}
@@ -0,0 +1,321 @@
+//! Mermaid flowchart export of `DiGraph`, for rendering graphs in Markdown
+//! documentation.
+
+use crate::formats::dot::weight_label;
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::{get_function_option, get_option, get_string_option};
+use crate::GraphError;
+use js_sys::{Function, Reflect};
+use petgraph::algo::tarjan_scc;
+use std::collections::HashMap;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Flowchart directions accepted by Mermaid.
+const DIRECTIONS: [&str; 5] = ["TB", "TD", "BT", "RL", "LR"];
+
+/// Escape text for a quoted Mermaid label. Characters that end the label or
+/// are read as markup are written as Mermaid entity codes and line breaks as
+/// `<br>`.
+pub fn escape_mermaid_label(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '&' => escaped.push_str("#amp;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            '\n' => escaped.push_str("<br>"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write a Mermaid flowchart. Nodes get the ids `n0`, `n1`, ... by index, and
+/// nodes with a group are declared in a subgraph per group, in order of the
+/// group's first node. Edges with an empty label are drawn without one.
+pub fn write_mermaid(
+    direction: &str,
+    node_labels: &[String],
+    groups: &[Option<String>],
+    edges: &[(usize, usize, String)],
+) -> String {
+    let mut group_nodes: Vec<(&str, Vec<usize>)> = Vec::new();
+    let mut group_indices = HashMap::new();
+    let mut out = String::new();
+    // Writing to a String can't fail.
+    let _ = writeln!(out, "flowchart {}", direction);
+    for (index, label) in node_labels.iter().enumerate() {
+        match groups.get(index).and_then(Option::as_deref) {
+            Some(group) => {
+                let group_index = *group_indices.entry(group).or_insert_with(|| {
+                    group_nodes.push((group, Vec::new()));
+                    group_nodes.len() - 1
+                });
+                group_nodes[group_index].1.push(index);
+            }
+            None => {
+                let _ = writeln!(out, "    n{}[\"{}\"]", index, escape_mermaid_label(label));
+            }
+        }
+    }
+    for (group_index, (group, nodes)) in group_nodes.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    subgraph g{}[\"{}\"]",
+            group_index,
+            escape_mermaid_label(group)
+        );
+        for &index in nodes {
+            let label = escape_mermaid_label(&node_labels[index]);
+            let _ = writeln!(out, "        n{}[\"{}\"]", index, label);
+        }
+        out.push_str("    end\n");
+    }
+    for (source, target, label) in edges {
+        if label.is_empty() {
+            let _ = writeln!(out, "    n{} --> n{}", source, target);
+        } else {
+            let label = escape_mermaid_label(label);
+            let _ = writeln!(out, "    n{} -->|\"{}\"| n{}", source, label, target);
+        }
+    }
+    out
+}
+
+/// Label every weight with an optional `label(weight, index)` callback,
+/// falling back to `weight_label`.
+fn collect_labels<'a>(
+    callback: Option<Function>,
+    weights: impl Iterator<Item = &'a JsValue>,
+) -> Result<Vec<String>, JsValue> {
+    weights
+        .enumerate()
+        .map(|(index, weight)| match &callback {
+            Some(callback) => callback
+                .call2(&JsValue::NULL, weight, &JsValue::from(index as u32))
+                .map(|label| weight_label(&label)),
+            None => Ok(weight_label(weight)),
+        })
+        .collect()
+}
+
+fn group_label(value: &JsValue) -> Option<String> {
+    Some(weight_label(value)).filter(|label| !label.is_empty())
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Export the graph as a Mermaid flowchart. Nodes get the ids `n0`,
+    /// `n1`, ... by index, and labels are quoted with `"`, `#`, `&`, `<` and
+    /// `>` escaped. Options:
+    ///
+    /// * `direction` - one of `"TD"` (the default), `"TB"`, `"BT"`, `"LR"`
+    ///   and `"RL"`.
+    /// * `nodeLabel` - function called as `nodeLabel(weight, index)` returning
+    ///   the node's label, defaults to the weight as text.
+    /// * `edgeLabel` - the same as `nodeLabel` for edges. Edges with an empty
+    ///   label are drawn without one.
+    /// * `groupBy` - draw groups of nodes as subgraphs. Either `"scc"` for the
+    ///   strongly connected components of more than one node, or a function
+    ///   called as `groupBy(weight, index)` returning the name of the node's
+    ///   group, or `null` for none.
+    /// * `groupProperty` - group nodes by a property of their weight instead,
+    ///   which can't be combined with `groupBy`.
+    #[wasm_bindgen(js_name = toMermaid)]
+    pub fn to_mermaid(&self, options: JsValue) -> Result<String, JsValue> {
+        let direction = get_string_option(&options, "direction")?.unwrap_or_else(|| "TD".into());
+        if !DIRECTIONS.contains(&direction.as_str()) {
+            return Err(GraphError::from_message(
+                "Option `direction` must be one of \"TB\", \"TD\", \"BT\", \"RL\" and \"LR\"",
+            )
+            .to_js_value());
+        }
+        let node_labels = collect_labels(
+            get_function_option(&options, "nodeLabel")?,
+            self.graph.raw_nodes().iter().map(|node| &node.weight),
+        )?;
+        let edge_labels = collect_labels(
+            get_function_option(&options, "edgeLabel")?,
+            self.graph.raw_edges().iter().map(|edge| &edge.weight),
+        )?;
+
+        let group_property = get_string_option(&options, "groupProperty")?;
+        let group_by = get_option(&options, "groupBy")?;
+        if group_property.is_some() && group_by.is_some() {
+            return Err(GraphError::from_message(
+                "Options `groupBy` and `groupProperty` can't be used together",
+            )
+            .to_js_value());
+        }
+        let mut groups = vec![None; self.graph.node_count()];
+        if let Some(property) = group_property {
+            let property = JsValue::from_str(&property);
+            for (group, node) in groups.iter_mut().zip(self.graph.raw_nodes()) {
+                if node.weight.is_object() {
+                    *group = group_label(&Reflect::get(&node.weight, &property)?);
+                }
+            }
+        }
+        match group_by {
+            None => {}
+            Some(group_by) if group_by.as_string().as_deref() == Some("scc") => {
+                let components = tarjan_scc(&self.graph);
+                // tarjan_scc returns the components in reverse topological order.
+                let components = components.iter().rev().filter(|nodes| nodes.len() > 1);
+                for (component, nodes) in components.enumerate() {
+                    for node in nodes {
+                        groups[node.index()] = Some(format!("SCC {}", component + 1));
+                    }
+                }
+            }
+            Some(group_by) if group_by.is_function() => {
+                let group_by = Function::from(group_by);
+                for (index, node) in self.graph.raw_nodes().iter().enumerate() {
+                    let group = group_by.call2(
+                        &JsValue::NULL,
+                        &node.weight,
+                        &JsValue::from(index as u32),
+                    )?;
+                    groups[index] = group_label(&group);
+                }
+            }
+            Some(_) => {
+                return Err(GraphError::from_message(
+                    "Option `groupBy` must be \"scc\" or a function",
+                )
+                .to_js_value())
+            }
+        }
+
+        let edges: Vec<(usize, usize, String)> = self
+            .graph
+            .raw_edges()
+            .iter()
+            .zip(edge_labels)
+            .map(|(edge, label)| (edge.source().index(), edge.target().index(), label))
+            .collect();
+        Ok(write_mermaid(&direction, &node_labels, &groups, &edges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use js_sys::Object;
+    use wasm_bindgen_test::*;
+
+    fn options(key: &str, value: &JsValue) -> JsValue {
+        let options = Object::new();
+        Reflect::set(&options, &JsValue::from_str(key), value).unwrap();
+        options.into()
+    }
+
+    #[test]
+    fn can_escape_mermaid_labels() {
+        assert_eq!(escape_mermaid_label("NYC"), "NYC");
+        assert_eq!(
+            escape_mermaid_label("say \"hi\" #1 <b>&\r\nbye"),
+            "say #quot;hi#quot; #35;1 #lt;b#gt;#amp;<br>bye"
+        );
+    }
+
+    #[test]
+    fn can_write_mermaid() {
+        let labels: Vec<String> = vec!["a".into(), "b".into(), "c".into(), "d".into()];
+        let groups = vec![None, Some("x".into()), None, Some("x".into())];
+        let edges = vec![(0, 1, String::new()), (1, 3, "2 km".into())];
+        assert_eq!(
+            write_mermaid("LR", &labels, &groups, &edges),
+            "flowchart LR\n    n0[\"a\"]\n    n2[\"c\"]\n    subgraph g0[\"x\"]\n        \
+             n1[\"b\"]\n        n3[\"d\"]\n    end\n    n0 --> n1\n    n1 -->|\"2 km\"| n3\n"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_export_mermaid() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let mermaid = g.to_mermaid(JsValue::UNDEFINED).unwrap();
+        assert!(mermaid.starts_with("flowchart TD\n    n0[\"NYC\"]\n"));
+        assert!(mermaid.contains("    n1 --> n0\n"));
+        assert!(g
+            .to_mermaid(options("direction", &JsValue::from_str("up")))
+            .is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_group_by_scc() {
+        // The cycle of nodes 2 and 3 comes before the cycle of nodes 0 and 1
+        // in topological order, so it's the first component.
+        let mut g = DiGraph::new(None, None, JsValue::UNDEFINED).unwrap();
+        for name in &["a", "b", "c", "d", "e"] {
+            g.add_node(JsValue::from_str(name));
+        }
+        for &(a, b) in &[(0, 1), (1, 0), (2, 3), (3, 2), (3, 0), (1, 4)] {
+            g.add_edge(a, b, JsValue::NULL).unwrap();
+        }
+        let mermaid = g
+            .to_mermaid(options("groupBy", &JsValue::from_str("scc")))
+            .unwrap();
+        assert!(mermaid.starts_with(
+            "flowchart TD\n    n4[\"e\"]\n    subgraph g0[\"SCC 2\"]\n        n0[\"a\"]\n        \
+             n1[\"b\"]\n    end\n    subgraph g1[\"SCC 1\"]\n        n2[\"c\"]\n        \
+             n3[\"d\"]\n    end\n"
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_group_by_function() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let group_by = Function::new_with_args("weight, index", "return index % 2 ? 'odd' : null");
+        let mermaid = g.to_mermaid(options("groupBy", &group_by)).unwrap();
+        assert!(mermaid.contains(
+            "    subgraph g0[\"odd\"]\n        n1[\"Vilnius\"]\n        n3[\"Taipei\"]\n    end\n"
+        ));
+        let group_by = Function::new_with_args("weight, index", "throw new Error('no')");
+        assert!(g.to_mermaid(options("groupBy", &group_by)).is_err());
+        assert!(g
+            .to_mermaid(options("groupBy", &JsValue::from_str("weakly")))
+            .is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_group_by_property() {
+        let mut g = DiGraph::new(None, None, JsValue::UNDEFINED).unwrap();
+        for team in &[Some("x"), None, Some("x")] {
+            let weight = Object::new();
+            if let Some(team) = team {
+                Reflect::set(
+                    &weight,
+                    &JsValue::from_str("team"),
+                    &JsValue::from_str(team),
+                )
+                .unwrap();
+            }
+            g.add_node(weight.into());
+        }
+        let options = options("groupProperty", &JsValue::from_str("team"));
+        let mermaid = g.to_mermaid(options.clone()).unwrap();
+        assert!(mermaid.contains("    subgraph g0[\"x\"]\n        n0["));
+        assert!(mermaid.contains("        n2["));
+        assert_eq!(mermaid.matches("subgraph").count(), 1);
+
+        Reflect::set(
+            &options,
+            &JsValue::from_str("groupBy"),
+            &JsValue::from_str("scc"),
+        )
+        .unwrap();
+        let err: GraphError = g.to_mermaid(options).unwrap_err().into_serde().unwrap();
+        assert_eq!(
+            err,
+            GraphError::from_message(
+                "Options `groupBy` and `groupProperty` can't be used together"
+            )
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Import and export of graphs in file and interchange formats. Each format
+//! lives in its own module and adds its methods to `DiGraph`.
+
+pub mod adjacency_matrix;
+pub mod bytes;
+pub mod csv;
+pub mod cytoscape;
+pub mod dot;
+pub mod graph6;
+pub mod graphml;
+pub mod graphology;
+pub mod json;
+pub mod loader;
+pub mod matrix_market;
+pub mod mermaid;
+pub mod node_link;
+
+use crate::formats::json::JsonGraph;
+use crate::{GraphError, GraphItemType};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Largest node count read from a size declared by the input, such as the
+/// shape of a sparse matrix, which isn't bounded by the length of the data
+/// and could otherwise make a few bytes allocate billions of nodes.
+pub const MAX_NODE_COUNT: usize = 1 << 24;
+
+/// Get the id of every node for formats which identify nodes by string. Nodes
+/// get the ids `n0`, `n1`, ... by index, or the value of their `id_property`,
+/// which has to be a unique string or number.
+pub fn node_ids(graph: &JsonGraph, id_property: Option<&str>) -> Result<Vec<String>, GraphError> {
+    let id_property = match id_property {
+        None => return Ok((0..graph.node_count()).map(|i| format!("n{}", i)).collect()),
+        Some(id_property) => id_property,
+    };
+    let mut seen = HashSet::new();
+    let mut ids = Vec::with_capacity(graph.node_count());
+    for (index, node) in graph.raw_nodes().iter().enumerate() {
+        let id = match id_text(node.weight.get(id_property)) {
+            Some(id) => id,
+            None => {
+                return Err(GraphError::new(
+                    &format!(
+                        "Node weight is missing a string or number `{}` property",
+                        id_property
+                    ),
+                    GraphItemType::Node,
+                    index as u32,
+                ))
+            }
+        };
+        if !seen.insert(id.clone()) {
+            return Err(GraphError::new(
+                &format!("Duplicate node id `{}`", id),
+                GraphItemType::Node,
+                index as u32,
+            ));
+        }
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Id of a node as a string, for ids given as strings or numbers.
+pub fn id_text(id: Option<&Value>) -> Option<String> {
+    match id? {
+        Value::String(id) => Some(id.clone()),
+        id @ Value::Number(_) => Some(id.to_string()),
+        _ => None,
+    }
+}
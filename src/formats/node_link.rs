@@ -0,0 +1,372 @@
+//! Node-link JSON as used by D3 force layouts, vis-network and networkx:
+//! `{ nodes: [{ id }], links: [{ source, target }] }`, with configurable field
+//! names.
+
+use crate::formats::id_text;
+use crate::formats::json::JsonGraph;
+use crate::graph_impl::DiGraph;
+use crate::js_helpers::options::get_string_option;
+use crate::{GraphError, GraphItemType};
+use petgraph::graph::NodeIndex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Field names of a node-link document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeLinkFields {
+    pub id: String,
+    /// Field holding the whole weight. Without it object weights are spread
+    /// into the node and link objects.
+    pub weight: Option<String>,
+    pub links: String,
+    pub source: String,
+    pub target: String,
+}
+
+impl Default for NodeLinkFields {
+    fn default() -> Self {
+        NodeLinkFields {
+            id: String::from("id"),
+            weight: None,
+            links: String::from("links"),
+            source: String::from("source"),
+            target: String::from("target"),
+        }
+    }
+}
+
+impl NodeLinkFields {
+    /// Read field names from a JS options object such as
+    /// `{ idField: "key", linksField: "edges", sourceField: "from" }`.
+    pub fn from_options(options: &JsValue) -> Result<Self, JsValue> {
+        let default = NodeLinkFields::default();
+        Ok(NodeLinkFields {
+            id: get_string_option(options, "idField")?.unwrap_or(default.id),
+            weight: get_string_option(options, "weightField")?,
+            links: get_string_option(options, "linksField")?.unwrap_or(default.links),
+            source: get_string_option(options, "sourceField")?.unwrap_or(default.source),
+            target: get_string_option(options, "targetField")?.unwrap_or(default.target),
+        })
+    }
+
+    /// Create a node or link object from a weight and the fields which
+    /// identify it. Weight properties with the name of one of the fields are
+    /// rejected rather than replaced.
+    fn to_object(
+        &self,
+        weight: &Value,
+        fields: Vec<(&str, Value)>,
+        item_type: GraphItemType,
+        index: usize,
+    ) -> Result<Value, GraphError> {
+        let mut object = Map::new();
+        match (&self.weight, weight) {
+            (_, Value::Null) => {}
+            (Some(weight_field), _) => {
+                object.insert(weight_field.clone(), weight.clone());
+            }
+            (None, Value::Object(properties)) => object.extend(properties.clone()),
+            (None, _) => {
+                object.insert(String::from("weight"), weight.clone());
+            }
+        }
+        for (key, value) in fields {
+            if object.insert(String::from(key), value).is_some() {
+                return Err(GraphError::new(
+                    &format!(
+                        "Weight property `{}` clashes with a node-link field, use `weightField`",
+                        key
+                    ),
+                    item_type,
+                    index as u32,
+                ));
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// Get the weight of a node or link object without the given fields.
+    pub fn weight(&self, object: &Map<String, Value>, skip: &[&str]) -> Value {
+        match &self.weight {
+            Some(weight_field) => object.get(weight_field).cloned().unwrap_or(Value::Null),
+            None => Value::Object(
+                object
+                    .iter()
+                    .filter(|(key, _)| !skip.contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn invalid_node_link(message: &str) -> GraphError {
+    GraphError::from_message(&format!("Invalid node-link graph: {}", message))
+}
+
+/// Convert a graph to a node-link document with node indices as ids.
+pub fn to_node_link(graph: &JsonGraph, fields: &NodeLinkFields) -> Result<Value, GraphError> {
+    let nodes = graph
+        .raw_nodes()
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let id = vec![(fields.id.as_str(), Value::from(index))];
+            fields.to_object(&node.weight, id, GraphItemType::Node, index)
+        })
+        .collect::<Result<_, _>>()?;
+    let links = graph
+        .raw_edges()
+        .iter()
+        .enumerate()
+        .map(|(index, edge)| {
+            let endpoints = vec![
+                (fields.source.as_str(), Value::from(edge.source().index())),
+                (fields.target.as_str(), Value::from(edge.target().index())),
+            ];
+            fields.to_object(&edge.weight, endpoints, GraphItemType::Edge, index)
+        })
+        .collect::<Result<_, _>>()?;
+    let mut document = Map::new();
+    document.insert(String::from("nodes"), Value::Array(nodes));
+    document.insert(fields.links.clone(), Value::Array(links));
+    Ok(Value::Object(document))
+}
+
+fn array_field<'a>(
+    document: &'a Map<String, Value>,
+    field: &str,
+) -> Result<&'a [Value], GraphError> {
+    match document.get(field) {
+        None | Some(Value::Null) => Ok(&[]),
+        Some(Value::Array(values)) => Ok(values),
+        Some(_) => Err(invalid_node_link(&format!("`{}` must be an array", field))),
+    }
+}
+
+/// Convert a node-link document to a graph, adding nodes in order. Link
+/// endpoints are node ids or node objects, as left by a D3 force simulation.
+pub fn from_node_link(document: &Value, fields: &NodeLinkFields) -> Result<JsonGraph, GraphError> {
+    let document = document
+        .as_object()
+        .ok_or_else(|| invalid_node_link("expected an object"))?;
+    let nodes = array_field(document, "nodes")?;
+    let links = array_field(document, &fields.links)?;
+
+    let mut graph = JsonGraph::with_capacity(nodes.len(), links.len());
+    let mut node_indices = HashMap::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let node = node
+            .as_object()
+            .ok_or_else(|| invalid_node_link(&format!("node {} must be an object", index)))?;
+        let id = id_text(node.get(&fields.id)).ok_or_else(|| {
+            invalid_node_link(&format!(
+                "node {} is missing the `{}` field",
+                index, fields.id
+            ))
+        })?;
+        let node_index = graph.add_node(fields.weight(node, &[&fields.id]));
+        if node_indices.insert(id.clone(), node_index).is_some() {
+            return Err(invalid_node_link(&format!("duplicate node id `{}`", id)));
+        }
+    }
+    for (index, link) in links.iter().enumerate() {
+        let link = link
+            .as_object()
+            .ok_or_else(|| invalid_node_link(&format!("link {} must be an object", index)))?;
+        let mut endpoints = [NodeIndex::new(0); 2];
+        for (endpoint, field) in endpoints.iter_mut().zip(&[&fields.source, &fields.target]) {
+            let id = match link.get(*field) {
+                Some(Value::Object(node)) => id_text(node.get(&fields.id)),
+                value => id_text(value),
+            }
+            .ok_or_else(|| {
+                invalid_node_link(&format!("link {} is missing the `{}` field", index, field))
+            })?;
+            *endpoint = *node_indices.get(&id).ok_or_else(|| {
+                invalid_node_link(&format!("link {} references unknown node `{}`", index, id))
+            })?;
+        }
+        let weight = fields.weight(link, &[&fields.source, &fields.target]);
+        graph.add_edge(endpoints[0], endpoints[1], weight);
+    }
+    Ok(graph)
+}
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Export the graph as node-link JSON `{ nodes: [{ id }], links: [{ source,
+    /// target }] }` with node indices as ids, for D3 force layouts and similar.
+    /// Object weights are spread into the node and link objects and other
+    /// weights stored as `weight`. Weight properties named like the id or
+    /// endpoint fields throw a `GraphError`, use `weightField` to keep them.
+    /// Options:
+    ///
+    /// * `idField` - name of the node id field, defaults to `"id"`.
+    /// * `weightField` - store the whole weight in this field instead of
+    ///   spreading it.
+    /// * `linksField`, `sourceField` and `targetField` - names of the links
+    ///   array and endpoint fields, defaults to `"links"`, `"source"` and
+    ///   `"target"`. vis-network for example uses `"edges"`, `"from"` and
+    ///   `"to"`.
+    #[wasm_bindgen(js_name = toNodeLink)]
+    pub fn to_node_link(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let fields = NodeLinkFields::from_options(&options)?;
+        let document = self
+            .to_json_graph()
+            .and_then(|graph| to_node_link(&graph, &fields))
+            .map_err(|err| err.to_js_value())?;
+        JsValue::from_serde(&document).map_err(|_| JsValue::from_str("Failed to transform graph"))
+    }
+
+    /// Create a graph from node-link JSON, with the same options as
+    /// `toNodeLink`. Nodes are added in order and weights are the node and
+    /// link objects without their id and endpoint fields, or the value of
+    /// `weightField` if given. Link endpoints can be node ids or node objects,
+    /// as left by a D3 force simulation.
+    #[wasm_bindgen(js_name = fromNodeLink)]
+    pub fn from_node_link(document: JsValue, options: JsValue) -> Result<DiGraph, JsValue> {
+        let fields = NodeLinkFields::from_options(&options)?;
+        let document: Value = document
+            .into_serde()
+            .map_err(|err| invalid_node_link(&err.to_string()).to_js_value())?;
+        from_node_link(&document, &fields)
+            .and_then(|graph| DiGraph::from_json_graph(&graph))
+            .map_err(|err| err.to_js_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    fn new_json_graph() -> JsonGraph {
+        let mut graph = JsonGraph::new();
+        let a = graph.add_node(json!({ "name": "a", "id": "kept" }));
+        let b = graph.add_node(json!(2));
+        graph.add_edge(a, b, json!({ "value": 3 }));
+        graph.add_edge(b, b, Value::Null);
+        graph
+    }
+
+    #[test]
+    fn can_export_node_link() {
+        let mut graph = new_json_graph();
+        graph[NodeIndex::new(0)] = json!({ "name": "a" });
+        assert_eq!(
+            to_node_link(&graph, &NodeLinkFields::default()).unwrap(),
+            json!({
+                "nodes": [{ "id": 0, "name": "a" }, { "id": 1, "weight": 2 }],
+                "links": [{ "source": 0, "target": 1, "value": 3 }, { "source": 1, "target": 1 }]
+            })
+        );
+        let fields = NodeLinkFields {
+            weight: Some(String::from("data")),
+            links: String::from("edges"),
+            source: String::from("from"),
+            target: String::from("to"),
+            ..NodeLinkFields::default()
+        };
+        assert_eq!(
+            to_node_link(&new_json_graph(), &fields).unwrap(),
+            json!({
+                "nodes": [{ "id": 0, "data": { "name": "a", "id": "kept" } }, { "id": 1, "data": 2 }],
+                "edges": [{ "from": 0, "to": 1, "data": { "value": 3 } }, { "from": 1, "to": 1 }]
+            })
+        );
+    }
+
+    #[test]
+    fn can_round_trip_node_link() {
+        let fields = NodeLinkFields {
+            weight: Some(String::from("w")),
+            ..NodeLinkFields::default()
+        };
+        let graph = new_json_graph();
+        let imported = from_node_link(&to_node_link(&graph, &fields).unwrap(), &fields).unwrap();
+        assert_eq!(imported.raw_nodes()[0].weight, graph.raw_nodes()[0].weight);
+        assert_eq!(imported.raw_nodes()[1].weight, json!(2));
+        assert_eq!(imported.raw_edges()[1].weight, Value::Null);
+        assert_eq!(imported.raw_edges()[1].source(), NodeIndex::new(1));
+    }
+
+    #[test]
+    fn can_reject_clashing_weight_properties() {
+        let fields = NodeLinkFields::default();
+        assert_eq!(
+            to_node_link(&new_json_graph(), &fields).unwrap_err(),
+            GraphError::new(
+                "Weight property `id` clashes with a node-link field, use `weightField`",
+                GraphItemType::Node,
+                0
+            )
+        );
+        let mut graph = new_json_graph();
+        graph[NodeIndex::new(0)] = json!({ "name": "a" });
+        graph[petgraph::graph::EdgeIndex::new(1)] = json!({ "target": "b" });
+        assert_eq!(
+            to_node_link(&graph, &fields).unwrap_err(),
+            GraphError::new(
+                "Weight property `target` clashes with a node-link field, use `weightField`",
+                GraphItemType::Edge,
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn can_import_node_link() {
+        let fields = NodeLinkFields {
+            id: String::from("key"),
+            ..NodeLinkFields::default()
+        };
+        let document = json!({
+            "nodes": [{ "key": "a", "x": 1.5 }, { "key": 7 }],
+            "links": [
+                { "source": "a", "target": 7, "value": 1 },
+                { "source": { "key": 7, "x": 0 }, "target": { "key": "a" } }
+            ]
+        });
+        let graph = from_node_link(&document, &fields).unwrap();
+        assert_eq!(graph.raw_nodes()[0].weight, json!({ "x": 1.5 }));
+        assert_eq!(graph.raw_edges()[0].weight, json!({ "value": 1 }));
+        assert_eq!(graph.raw_edges()[1].source(), NodeIndex::new(1));
+        assert_eq!(graph.raw_edges()[1].target(), NodeIndex::new(0));
+    }
+
+    #[test]
+    fn can_reject_invalid_node_link() {
+        let error =
+            |document: Value| from_node_link(&document, &NodeLinkFields::default()).unwrap_err();
+        assert_eq!(
+            error(json!({ "nodes": [{ "name": "a" }] })),
+            invalid_node_link("node 0 is missing the `id` field")
+        );
+        assert_eq!(
+            error(json!({ "nodes": [{ "id": 1 }, { "id": 1 }] })),
+            invalid_node_link("duplicate node id `1`")
+        );
+        assert_eq!(
+            error(json!({ "nodes": [{ "id": 1 }], "links": [{ "source": 1 }] })),
+            invalid_node_link("link 0 is missing the `target` field")
+        );
+        assert_eq!(
+            error(json!({ "nodes": [{ "id": 1 }], "links": [{ "source": 1, "target": 2 }] })),
+            invalid_node_link("link 0 references unknown node `2`")
+        );
+        assert_eq!(error(json!([])), invalid_node_link("expected an object"));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_round_trip_node_link_js() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let document = g.to_node_link(JsValue::UNDEFINED).unwrap();
+        let imported = DiGraph::from_node_link(document, JsValue::UNDEFINED).unwrap();
+        assert_eq!(imported.node_count(), 5);
+        assert_eq!(imported.edge_endpoints_all(), g.edge_endpoints_all());
+    }
+}
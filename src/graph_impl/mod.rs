@@ -1,141 +1,547 @@
+#[macro_use]
+mod shared;
+mod policy;
+mod simplify;
+mod un_graph;
+
+pub use policy::GraphPolicy;
+pub use un_graph::UnGraph;
+
+use crate::js_helpers::index_remap::IndexRemap;
 use crate::utils;
+use crate::GraphError;
+use js_sys::{Function, Reflect};
 use petgraph::graph;
-use std::convert::TryInto;
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Incoming = "incoming",
     Outgoing = "outgoing",
+    Both = "both",
 }
 
-impl TryInto<petgraph::Direction> for Direction {
-    type Error = JsValue;
-    fn try_into(self) -> Result<petgraph::Direction, Self::Error> {
+impl Direction {
+    /// Get the petgraph directions to walk for this direction, where `Both`
+    /// walks the outgoing edges followed by the incoming edges. Strings from
+    /// Javascript which aren't a valid direction result in a `GraphError`.
+    pub fn petgraph_directions(self) -> Result<&'static [petgraph::Direction], GraphError> {
         match self {
-            Direction::Incoming => Ok(petgraph::Direction::Incoming),
-            Direction::Outgoing => Ok(petgraph::Direction::Outgoing),
-            _ => Err(JsValue::from_str("Invalid direction")),
+            Direction::Incoming => Ok(&[petgraph::Direction::Incoming]),
+            Direction::Outgoing => Ok(&[petgraph::Direction::Outgoing]),
+            Direction::Both => Ok(&[petgraph::Direction::Outgoing, petgraph::Direction::Incoming]),
+            _ => Err(GraphError::from_message(
+                "Invalid direction, expected \"incoming\", \"outgoing\" or \"both\"",
+            )),
+        }
+    }
+}
+
+/// Endpoints of every edge in a graph, stored as two parallel arrays so they
+/// can be handed to Javascript as `Uint32Array`s without per-edge allocations.
+/// The edge with index `i` goes from `sources[i]` to `targets[i]`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeEndpoints {
+    sources: Vec<u32>,
+    targets: Vec<u32>,
+}
+
+impl EdgeEndpoints {
+    pub fn from_edges(edges: &[graph::Edge<JsValue>]) -> Self {
+        let mut sources = Vec::with_capacity(edges.len());
+        let mut targets = Vec::with_capacity(edges.len());
+        for edge in edges {
+            sources.push(edge.source().index() as u32);
+            targets.push(edge.target().index() as u32);
+        }
+        EdgeEndpoints { sources, targets }
+    }
+}
+
+#[wasm_bindgen]
+impl EdgeEndpoints {
+    #[wasm_bindgen(getter)]
+    pub fn sources(&self) -> Vec<u32> {
+        self.sources.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn targets(&self) -> Vec<u32> {
+        self.targets.clone()
+    }
+}
+
+/// In-degree and out-degree of every node in a graph, indexed by node index.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Degrees {
+    incoming: Vec<u32>,
+    outgoing: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl Degrees {
+    #[wasm_bindgen(getter)]
+    pub fn incoming(&self) -> Vec<u32> {
+        self.incoming.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn outgoing(&self) -> Vec<u32> {
+        self.outgoing.clone()
+    }
+}
+
+/// Mapping from the node and edge indices a graph had before a bulk removal
+/// to their new indices, with `null` for removed items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexMapping {
+    pub nodes: Vec<Option<usize>>,
+    pub edges: Vec<Option<usize>>,
+}
+
+impl IndexMapping {
+    pub fn create_js_value(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self)
+            .map_err(|_| JsValue::from_str("Failed to transform index_mapping"))
+    }
+}
+
+/// Indices affected by removing a single node or edge. Moves are given as
+/// `[from, to]` pairs of indices.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexMoves {
+    pub removed_nodes: Vec<usize>,
+    pub removed_edges: Vec<usize>,
+    pub moved_nodes: Vec<(usize, usize)>,
+    pub moved_edges: Vec<(usize, usize)>,
+}
+
+impl IndexMoves {
+    pub fn new(node_remap: &IndexRemap, edge_remap: &IndexRemap) -> Self {
+        IndexMoves {
+            removed_nodes: node_remap.removed().to_vec(),
+            removed_edges: edge_remap.removed().to_vec(),
+            moved_nodes: node_remap.moved(),
+            moved_edges: edge_remap.moved(),
         }
     }
+
+    pub fn create_js_value(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self).map_err(|_| JsValue::from_str("Failed to transform index_moves"))
+    }
+
+    /// Create JS object of the moves with the removed item's `weight` added.
+    pub fn create_js_value_with_weight(&self, weight: &JsValue) -> Result<JsValue, JsValue> {
+        let moves = self.create_js_value()?;
+        Reflect::set(&moves, &JsValue::from_str("weight"), weight)?;
+        Ok(moves)
+    }
+}
+
+/// Call a Javascript predicate with `(weight, index)` for every item of a
+/// graph before any of them are removed, so that an exception leaves the graph
+/// untouched.
+fn evaluate_predicate<'a>(
+    predicate: &Function,
+    weights: impl Iterator<Item = &'a JsValue>,
+) -> Result<Vec<bool>, JsValue> {
+    weights
+        .enumerate()
+        .map(|(index, weight)| {
+            predicate
+                .call2(&JsValue::NULL, weight, &JsValue::from(index as u32))
+                .map(|keep| keep.is_truthy())
+        })
+        .collect()
+}
+
+/// Call a Javascript weight mapping function with `(weight, index)` and return
+/// its result. After the first exception is stored in `error` no further calls
+/// are made, as petgraph's mapping closures can't abort early.
+fn call_weight_map(
+    map: &Function,
+    weight: &JsValue,
+    index: usize,
+    error: &mut Option<JsValue>,
+) -> JsValue {
+    if error.is_some() {
+        return JsValue::UNDEFINED;
+    }
+    map.call2(&JsValue::NULL, weight, &JsValue::from(index as u32))
+        .unwrap_or_else(|err| {
+            *error = Some(err);
+            JsValue::UNDEFINED
+        })
+}
+
+/// A graph extracted from another graph, along with the mapping from its node
+/// and edge indices to the indices they had in the original graph.
+#[wasm_bindgen]
+pub struct Subgraph {
+    graph: DiGraph,
+    node_map: Vec<u32>,
+    edge_map: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl Subgraph {
+    /// Get the extracted graph. Each access returns a new copy.
+    #[wasm_bindgen(getter)]
+    pub fn graph(&self) -> DiGraph {
+        self.graph.clone()
+    }
+
+    /// Original node index for every node index of the extracted graph.
+    #[wasm_bindgen(getter, js_name = nodeMap)]
+    pub fn node_map(&self) -> Vec<u32> {
+        self.node_map.clone()
+    }
+
+    /// Original edge index for every edge index of the extracted graph.
+    #[wasm_bindgen(getter, js_name = edgeMap)]
+    pub fn edge_map(&self) -> Vec<u32> {
+        self.edge_map.clone()
+    }
 }
 
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct DiGraph {
     #[wasm_bindgen(skip)]
     pub graph: graph::DiGraph<JsValue, JsValue>,
+    #[wasm_bindgen(skip)]
+    pub policy: GraphPolicy,
 }
 
+impl_graph_methods!(DiGraph);
+
 #[wasm_bindgen]
 impl DiGraph {
+    /// Create a new graph. The optional `options` object sets the graph's
+    /// policy for new edges, see `GraphPolicy::from_options`.
     #[wasm_bindgen(constructor)]
-    pub fn new(node_capacity: Option<usize>, edge_capacity: Option<usize>) -> Self {
+    pub fn new(
+        node_capacity: Option<usize>,
+        edge_capacity: Option<usize>,
+        options: JsValue,
+    ) -> Result<DiGraph, JsValue> {
         utils::set_panic_hook();
-        DiGraph {
+        Ok(DiGraph {
             graph: graph::DiGraph::with_capacity(
-                node_capacity.or(Some(0)).unwrap(),
-                edge_capacity.or(Some(0)).unwrap(),
+                node_capacity.unwrap_or(0),
+                edge_capacity.unwrap_or(0),
             ),
-        }
+            policy: GraphPolicy::from_options(&options)?,
+        })
+    }
+
+    /// Create a copy of the graph with identical indices.
+    #[wasm_bindgen(js_name = clone)]
+    pub fn clone_graph(&self) -> DiGraph {
+        self.clone()
     }
 
-    #[wasm_bindgen(js_name = nodeCount)]
-    pub fn node_count(&self) -> usize {
-        self.graph.node_count()
+    /// Reverse the direction of all edges in place.
+    pub fn reverse(&mut self) {
+        self.graph.reverse()
     }
 
-    #[wasm_bindgen(js_name = edgeCount)]
-    pub fn edge_count(&self) -> usize {
-        self.graph.edge_count()
+    /// Create an undirected copy of the graph with identical indices and
+    /// weights.
+    #[wasm_bindgen(js_name = toUndirected)]
+    pub fn to_undirected(&self) -> UnGraph {
+        UnGraph::from(self.graph.clone().into_edge_type())
     }
 
-    #[wasm_bindgen(js_name = isDirected)]
-    pub fn is_directed(&self) -> bool {
-        self.graph.is_directed()
+    #[wasm_bindgen(js_name = nodeCapacity)]
+    pub fn node_capacity(&self) -> usize {
+        self.graph.capacity().0
     }
 
-    #[wasm_bindgen(js_name = addNode)]
-    pub fn add_node(&mut self, weight: JsValue) -> usize {
-        self.graph.add_node(weight).index()
+    #[wasm_bindgen(js_name = edgeCapacity)]
+    pub fn edge_capacity(&self) -> usize {
+        self.graph.capacity().1
     }
 
-    #[wasm_bindgen(js_name = nodeWeight)]
-    pub fn node_weight(&self, index: usize) -> Result<JsValue, JsValue> {
-        match self.graph.node_weight(graph::NodeIndex::new(index)) {
-            Some(weight) => Ok(weight.clone()),
+    #[wasm_bindgen(js_name = reserveNodes)]
+    pub fn reserve_nodes(&mut self, additional: usize) {
+        self.graph.reserve_nodes(additional)
+    }
+
+    #[wasm_bindgen(js_name = reserveEdges)]
+    pub fn reserve_edges(&mut self, additional: usize) {
+        self.graph.reserve_edges(additional)
+    }
+
+    #[wasm_bindgen(js_name = shrinkToFit)]
+    pub fn shrink_to_fit(&mut self) {
+        self.graph.shrink_to_fit()
+    }
+
+    /// Remove all nodes and edges while keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.graph.clear()
+    }
+
+    /// Remove all edges while keeping the nodes and the allocated capacity.
+    #[wasm_bindgen(js_name = clearEdges)]
+    pub fn clear_edges(&mut self) {
+        self.graph.clear_edges()
+    }
+
+    /// Replace the weight of a node, returning the previous weight.
+    #[wasm_bindgen(js_name = setNodeWeight)]
+    pub fn set_node_weight(&mut self, index: usize, weight: JsValue) -> Result<JsValue, JsValue> {
+        match self.graph.node_weight_mut(graph::NodeIndex::new(index)) {
+            Some(current) => Ok(std::mem::replace(current, weight)),
             None => Err(JsValue::from_str("No node exists for given index")),
         }
     }
 
+    /// Add an edge from `a` to `b`, throwing a `GraphError` if the edge
+    /// violates the graph's policy.
     #[wasm_bindgen(js_name = addEdge)]
-    pub fn add_edge(&mut self, a: usize, b: usize, weight: JsValue) -> usize {
-        self.graph
-            .add_edge(graph::NodeIndex::new(a), graph::NodeIndex::new(b), weight)
-            .index()
+    pub fn add_edge(&mut self, a: usize, b: usize, weight: JsValue) -> Result<usize, JsValue> {
+        let (a, b) = (graph::NodeIndex::new(a), graph::NodeIndex::new(b));
+        self.check_new_edge(a, b).map_err(|err| err.to_js_value())?;
+        Ok(self.graph.add_edge(a, b, weight).index())
     }
 
+    /// Update the weight of the edge from `a` to `b`, or add it if it doesn't
+    /// exist yet, in which case the graph's policy is checked like `addEdge`.
     #[wasm_bindgen(js_name = updateEdge)]
-    pub fn update_edge(&mut self, a: usize, b: usize, weight: JsValue) -> usize {
-        self.graph
-            .update_edge(graph::NodeIndex::new(a), graph::NodeIndex::new(b), weight)
-            .index()
+    pub fn update_edge(&mut self, a: usize, b: usize, weight: JsValue) -> Result<usize, JsValue> {
+        let (a, b) = (graph::NodeIndex::new(a), graph::NodeIndex::new(b));
+        if self.graph.find_edge(a, b).is_none() {
+            self.check_new_edge(a, b).map_err(|err| err.to_js_value())?;
+        }
+        Ok(self.graph.update_edge(a, b, weight).index())
     }
 
-    #[wasm_bindgen(js_name = edgeWeight)]
-    pub fn edge_weight(&self, index: usize) -> Result<JsValue, JsValue> {
-        match self.graph.edge_weight(graph::EdgeIndex::new(index)) {
-            Some(weight) => Ok(weight.clone()),
+    /// Replace the weight of an edge, returning the previous weight.
+    #[wasm_bindgen(js_name = setEdgeWeight)]
+    pub fn set_edge_weight(&mut self, index: usize, weight: JsValue) -> Result<JsValue, JsValue> {
+        match self.graph.edge_weight_mut(graph::EdgeIndex::new(index)) {
+            Some(current) => Ok(std::mem::replace(current, weight)),
             None => Err(JsValue::from_str("No edge exists for given index")),
         }
     }
 
-    #[wasm_bindgen(js_name = edgeEndpoints)]
-    pub fn edge_endpoints(&self, index: usize) -> Option<Vec<usize>> {
+    #[wasm_bindgen(js_name = nodeIndices)]
+    pub fn node_indices(&self) -> Vec<u32> {
         self.graph
-            .edge_endpoints(graph::EdgeIndex::new(index))
-            .map(|(i1, i2)| vec![i1.index(), i2.index()])
+            .node_indices()
+            .map(|node| node.index() as u32)
+            .collect()
+    }
+
+    #[wasm_bindgen(js_name = edgeIndices)]
+    pub fn edge_indices(&self) -> Vec<u32> {
+        self.graph
+            .edge_indices()
+            .map(|edge| edge.index() as u32)
+            .collect()
+    }
+
+    /// Create a new graph with the same structure and indices where each node
+    /// weight is replaced by the result of `map(weight, index)`.
+    #[wasm_bindgen(js_name = mapNodes)]
+    pub fn map_nodes(&self, map: &Function) -> Result<DiGraph, JsValue> {
+        let mut error = None;
+        let graph = self.graph.map(
+            |node, weight| call_weight_map(map, weight, node.index(), &mut error),
+            |_, weight| weight.clone(),
+        );
+        match error {
+            Some(err) => Err(err),
+            None => Ok(self.with_graph(graph)),
+        }
+    }
+
+    /// Create a new graph with the same structure and indices where each edge
+    /// weight is replaced by the result of `map(weight, index)`.
+    #[wasm_bindgen(js_name = mapEdges)]
+    pub fn map_edges(&self, map: &Function) -> Result<DiGraph, JsValue> {
+        let mut error = None;
+        let graph = self.graph.map(
+            |_, weight| weight.clone(),
+            |edge, weight| call_weight_map(map, weight, edge.index(), &mut error),
+        );
+        match error {
+            Some(err) => Err(err),
+            None => Ok(self.with_graph(graph)),
+        }
+    }
+
+    /// Keep only the nodes for which `predicate(weight, index)` is truthy,
+    /// removing all others along with their edges. Returns the mapping from old
+    /// to new node and edge indices.
+    #[wasm_bindgen(js_name = retainNodes)]
+    pub fn retain_nodes(&mut self, predicate: &Function) -> Result<JsValue, JsValue> {
+        let keep = evaluate_predicate(
+            predicate,
+            self.graph.raw_nodes().iter().map(|node| &node.weight),
+        )?;
+        let mut node_remap = IndexRemap::new(self.graph.node_count());
+        let mut edge_remap = IndexRemap::new(self.graph.edge_count());
+        // Same order as petgraph's `Graph::retain_nodes`, walking backwards
+        // means the yet to be visited indices are never relocated.
+        for node in self.graph.node_indices().rev() {
+            if !keep[node.index()] {
+                self.remove_node_tracked(node, &mut node_remap, &mut edge_remap);
+            }
+        }
+        IndexMapping {
+            nodes: node_remap.mapping(),
+            edges: edge_remap.mapping(),
+        }
+        .create_js_value()
+    }
+
+    /// Keep only the edges for which `predicate(weight, index)` is truthy,
+    /// removing all others. Returns the mapping from old to new node and edge
+    /// indices, where node indices are unchanged.
+    #[wasm_bindgen(js_name = retainEdges)]
+    pub fn retain_edges(&mut self, predicate: &Function) -> Result<JsValue, JsValue> {
+        let keep = evaluate_predicate(
+            predicate,
+            self.graph.raw_edges().iter().map(|edge| &edge.weight),
+        )?;
+        let mut edge_remap = IndexRemap::new(self.graph.edge_count());
+        for edge in self.graph.edge_indices().rev() {
+            if !keep[edge.index()] {
+                edge_remap.swap_remove(edge.index());
+                self.graph.remove_edge(edge);
+            }
+        }
+        IndexMapping {
+            nodes: (0..self.graph.node_count()).map(Some).collect(),
+            edges: edge_remap.mapping(),
+        }
+        .create_js_value()
     }
 
-    #[wasm_bindgen(js_name = removeNode)]
-    pub fn remove_node(&mut self, index: usize) -> Result<JsValue, JsValue> {
-        match self.graph.remove_node(graph::NodeIndex::new(index)) {
-            Some(node) => Ok(node),
+    /// Extract the subgraph made of the given nodes and all edges between them.
+    #[wasm_bindgen(js_name = inducedSubgraph)]
+    pub fn induced_subgraph(&self, node_ids: &[u32]) -> Result<Subgraph, JsValue> {
+        let mut keep_nodes = vec![false; self.graph.node_count()];
+        for &node_id in node_ids {
+            match keep_nodes.get_mut(node_id as usize) {
+                Some(keep) => *keep = true,
+                None => return Err(JsValue::from_str("No node exists for given index")),
+            }
+        }
+        let keep_edges = vec![true; self.graph.edge_count()];
+        Ok(self.filter_subgraph(&keep_nodes, &keep_edges))
+    }
+
+    /// Extract the subgraph made of the given edges and their endpoints.
+    #[wasm_bindgen(js_name = edgeSubgraph)]
+    pub fn edge_subgraph(&self, edge_ids: &[u32]) -> Result<Subgraph, JsValue> {
+        let mut keep_nodes = vec![false; self.graph.node_count()];
+        let mut keep_edges = vec![false; self.graph.edge_count()];
+        for &edge_id in edge_ids {
+            let edge = graph::EdgeIndex::new(edge_id as usize);
+            match self.graph.edge_endpoints(edge) {
+                Some((source, target)) => {
+                    keep_edges[edge.index()] = true;
+                    keep_nodes[source.index()] = true;
+                    keep_nodes[target.index()] = true;
+                }
+                None => return Err(JsValue::from_str("No edge exists for given index")),
+            }
+        }
+        Ok(self.filter_subgraph(&keep_nodes, &keep_edges))
+    }
+
+    /// Get the in-degree and out-degree of every node in a single pass over
+    /// the edges.
+    pub fn degrees(&self) -> Degrees {
+        let mut incoming = vec![0; self.graph.node_count()];
+        let mut outgoing = vec![0; self.graph.node_count()];
+        for edge in self.graph.raw_edges() {
+            outgoing[edge.source().index()] += 1;
+            incoming[edge.target().index()] += 1;
+        }
+        Degrees { incoming, outgoing }
+    }
+
+    /// Remove a node like `removeNode`, but return an object with the removed
+    /// `weight` and which node and edge indices were removed or moved.
+    #[wasm_bindgen(js_name = removeNodeWithRemap)]
+    pub fn remove_node_with_remap(&mut self, index: usize) -> Result<JsValue, JsValue> {
+        let mut node_remap = IndexRemap::new(self.graph.node_count());
+        let mut edge_remap = IndexRemap::new(self.graph.edge_count());
+        match self.remove_node_tracked(
+            graph::NodeIndex::new(index),
+            &mut node_remap,
+            &mut edge_remap,
+        ) {
+            Some(node) => {
+                IndexMoves::new(&node_remap, &edge_remap).create_js_value_with_weight(&node)
+            }
             None => Err(JsValue::from_str("No node exists for given index")),
         }
     }
 
-    #[wasm_bindgen(js_name = removeEdge)]
-    pub fn remove_edge(&mut self, index: usize) -> Result<JsValue, JsValue> {
+    /// Remove an edge like `removeEdge`, but return an object with the removed
+    /// `weight` and which edge index was moved into its place.
+    #[wasm_bindgen(js_name = removeEdgeWithRemap)]
+    pub fn remove_edge_with_remap(&mut self, index: usize) -> Result<JsValue, JsValue> {
+        let node_remap = IndexRemap::new(self.graph.node_count());
+        let mut edge_remap = IndexRemap::new(self.graph.edge_count());
         match self.graph.remove_edge(graph::EdgeIndex::new(index)) {
-            Some(edge) => Ok(edge),
+            Some(edge) => {
+                edge_remap.swap_remove(index);
+                IndexMoves::new(&node_remap, &edge_remap).create_js_value_with_weight(&edge)
+            }
             None => Err(JsValue::from_str("No edge exists for given index")),
         }
     }
 
-    pub fn neighbors(&mut self, index: usize) -> Option<Vec<usize>> {
-        let neighbor_vec: Vec<usize> = self
-            .graph
-            .neighbors(graph::NodeIndex::new(index))
-            .map(|neighbor: graph::NodeIndex| neighbor.index())
-            .collect();
-        Some(neighbor_vec)
-    }
-
+    /// Get the neighbors of a node in the given direction, where `Both` is
+    /// the same as `neighborsUndirected`.
     #[wasm_bindgen(js_name = neighborsDirected)]
     pub fn neighbors_directed(
         &mut self,
         index: usize,
         direction: Direction,
     ) -> Result<Vec<usize>, JsValue> {
-        let node_index = graph::NodeIndex::new(index);
-        let neighbor_vec: Vec<usize> = self
-            .graph
-            .neighbors_directed(node_index, direction.try_into()?)
-            .map(|neighbor: graph::NodeIndex| neighbor.index())
-            .collect();
+        let pairs = self.directed_edge_pairs(index, direction)?;
+        let neighbor_vec: Vec<usize> = pairs.into_iter().map(|(_, neighbor)| neighbor).collect();
         Ok(neighbor_vec)
     }
 
+    /// Get the indices of the edges of a node in the given direction.
+    #[wasm_bindgen(js_name = edgesDirected)]
+    pub fn edges_directed(
+        &self,
+        index: usize,
+        direction: Direction,
+    ) -> Result<Vec<usize>, JsValue> {
+        let pairs = self.directed_edge_pairs(index, direction)?;
+        let edge_vec: Vec<usize> = pairs.into_iter().map(|(edge, _)| edge).collect();
+        Ok(edge_vec)
+    }
+
+    /// Get the number of edges of a node in the given direction, where `Both`
+    /// counts self loops twice.
+    pub fn degree(&self, index: usize, direction: Direction) -> Result<usize, JsValue> {
+        let node_index = graph::NodeIndex::new(index);
+        let directions = direction
+            .petgraph_directions()
+            .map_err(|err| err.to_js_value())?;
+        Ok(directions
+            .iter()
+            .map(|&d| self.graph.edges_directed(node_index, d).count())
+            .sum())
+    }
+
     #[wasm_bindgen(js_name = neighborsUndirected)]
     pub fn neighbors_undirected(&mut self, index: usize) -> Option<Vec<usize>> {
         let neighbor_vec: Vec<usize> = self
@@ -145,6 +551,126 @@ impl DiGraph {
             .collect();
         Some(neighbor_vec)
     }
+
+    /// Get the nodes without edges in the given direction, so `Incoming`
+    /// returns the sources of the graph, `Outgoing` returns its sinks and
+    /// `Both` returns the isolated nodes.
+    pub fn externals(&self, direction: Direction) -> Result<Vec<usize>, JsValue> {
+        let directions = direction
+            .petgraph_directions()
+            .map_err(|err| err.to_js_value())?;
+        let external_vec: Vec<usize> = self
+            .graph
+            .node_indices()
+            .filter(|&node| {
+                directions
+                    .iter()
+                    .all(|&d| self.graph.first_edge(node, d).is_none())
+            })
+            .map(|node: graph::NodeIndex| node.index())
+            .collect();
+        Ok(external_vec)
+    }
+}
+
+impl From<graph::DiGraph<JsValue, JsValue>> for DiGraph {
+    fn from(graph: graph::DiGraph<JsValue, JsValue>) -> Self {
+        DiGraph {
+            graph,
+            policy: GraphPolicy::default(),
+        }
+    }
+}
+
+impl DiGraph {
+    /// Get the `(edge, neighbor)` index pairs of a node in the given
+    /// direction. `Both` walks the outgoing edges followed by the incoming
+    /// edges and, like petgraph's undirected walks, reports self loops once.
+    fn directed_edge_pairs(
+        &self,
+        index: usize,
+        direction: Direction,
+    ) -> Result<Vec<(usize, usize)>, JsValue> {
+        let node_index = graph::NodeIndex::new(index);
+        let directions = direction
+            .petgraph_directions()
+            .map_err(|err| err.to_js_value())?;
+        let mut pairs = Vec::new();
+        for &d in directions {
+            for edge in self.graph.edges_directed(node_index, d) {
+                let is_loop = edge.source() == edge.target();
+                if direction == Direction::Both && d == petgraph::Direction::Incoming && is_loop {
+                    continue;
+                }
+                let neighbor = match d {
+                    petgraph::Direction::Outgoing => edge.target(),
+                    petgraph::Direction::Incoming => edge.source(),
+                };
+                pairs.push((edge.id().index(), neighbor.index()));
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// Wrap a graph derived from this one, such as a subgraph, keeping this
+    /// graph's policy.
+    fn with_graph(&self, graph: graph::DiGraph<JsValue, JsValue>) -> DiGraph {
+        DiGraph {
+            graph,
+            policy: self.policy,
+        }
+    }
+
+    /// Copy the flagged nodes and edges into a new graph, edges are only kept
+    /// if both of their endpoints are kept.
+    fn filter_subgraph(&self, keep_nodes: &[bool], keep_edges: &[bool]) -> Subgraph {
+        let mut node_map = Vec::new();
+        let mut edge_map = Vec::new();
+        // `filter_map` adds the kept items in index order, and only calls the
+        // edge closure for edges whose endpoints were kept.
+        let graph = self.graph.filter_map(
+            |node, weight| {
+                if keep_nodes[node.index()] {
+                    node_map.push(node.index() as u32);
+                    Some(weight.clone())
+                } else {
+                    None
+                }
+            },
+            |edge, weight| {
+                if keep_edges[edge.index()] {
+                    edge_map.push(edge.index() as u32);
+                    Some(weight.clone())
+                } else {
+                    None
+                }
+            },
+        );
+        Subgraph {
+            graph: self.with_graph(graph),
+            node_map,
+            edge_map,
+        }
+    }
+
+    /// Remove a node like `Graph::remove_node` while recording every node and
+    /// edge index which was removed or relocated in the given remaps.
+    pub fn remove_node_tracked(
+        &mut self,
+        node: graph::NodeIndex,
+        node_remap: &mut IndexRemap,
+        edge_remap: &mut IndexRemap,
+    ) -> Option<JsValue> {
+        self.graph.node_weight(node)?;
+        for &direction in &[petgraph::Direction::Outgoing, petgraph::Direction::Incoming] {
+            while let Some(edge) = self.graph.first_edge(node, direction) {
+                edge_remap.swap_remove(edge.index());
+                self.graph.remove_edge(edge);
+            }
+        }
+        node_remap.swap_remove(node.index());
+        self.graph.remove_node(node)
+    }
 }
 
 #[cfg(test)]
@@ -162,6 +688,32 @@ mod tests {
         assert_eq!(edges, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
     }
 
+    #[wasm_bindgen_test]
+    fn can_manage_capacity() {
+        let mut g = DiGraph::new(Some(10), Some(20), JsValue::UNDEFINED).unwrap();
+        assert!(g.node_capacity() >= 10);
+        assert!(g.edge_capacity() >= 20);
+        g.reserve_nodes(100);
+        g.reserve_edges(200);
+        assert!(g.node_capacity() >= 100);
+        assert!(g.edge_capacity() >= 200);
+        g.shrink_to_fit();
+        assert_eq!(g.node_capacity(), 0);
+        assert_eq!(g.edge_capacity(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_clear_graph() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        g.clear_edges();
+        assert_eq!(g.node_count(), 5);
+        assert_eq!(g.edge_count(), 0);
+        assert_eq!(g.neighbors(1), Some(vec![]));
+        g.clear();
+        assert_eq!(g.node_count(), 0);
+        assert!(g.node_capacity() >= 5);
+    }
+
     #[wasm_bindgen_test]
     fn can_get_nodes_by_index() {
         let (g, _nodes, _edges) = new_test_graph();
@@ -172,4 +724,257 @@ mod tests {
         assert_eq!(vilnius_name, "Vilnius");
         assert_eq!(na_name, JsValue::from_str("No node exists for given index"));
     }
+
+    #[wasm_bindgen_test]
+    fn can_list_nodes_and_edges() {
+        let (g, nodes, edges) = new_test_graph();
+        assert_eq!(g.node_indices(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(g.edge_indices(), vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        let weights = g.node_weights();
+        assert_eq!(weights.len(), nodes.len());
+        assert_eq!(weights[3], "Taipei");
+        assert_eq!(g.edge_weights().len(), edges.len());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_get_all_edge_endpoints() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let endpoints = g.edge_endpoints_all();
+        assert_eq!(endpoints.sources(), vec![1, 1, 1, 0, 0, 3, 4, 4, 4]);
+        assert_eq!(endpoints.targets(), vec![0, 3, 2, 3, 2, 2, 1, 0, 3]);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_set_weights() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        let old_name = g.set_node_weight(2, JsValue::from_str("Chattanooga"));
+        assert_eq!(old_name.unwrap(), "Knoxville");
+        assert_eq!(g.node_weight(2).unwrap(), "Chattanooga");
+        assert_eq!(
+            g.set_edge_weight(5, JsValue::from(7)).unwrap(),
+            JsValue::NULL
+        );
+        assert_eq!(g.edge_weight(5).unwrap(), 7);
+        assert!(g.set_node_weight(9999, JsValue::NULL).is_err());
+        assert!(g.set_edge_weight(9999, JsValue::NULL).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_map_weights() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let tag = Function::new_with_args("weight, index", "return `${weight}#${index}`");
+        let mapped_nodes = g.map_nodes(&tag).unwrap();
+        assert_eq!(mapped_nodes.node_weight(1).unwrap(), "Vilnius#1");
+        assert_eq!(mapped_nodes.edge_weight(1).unwrap(), JsValue::NULL);
+        let mapped_edges = g.map_edges(&tag).unwrap();
+        assert_eq!(mapped_edges.node_weight(1).unwrap(), "Vilnius");
+        assert_eq!(mapped_edges.edge_weight(8).unwrap(), "null#8");
+        assert_eq!(mapped_edges.edge_endpoints(8), g.edge_endpoints(8));
+
+        let throws = Function::new_with_args("", "throw new Error('nope')");
+        assert!(g.map_nodes(&throws).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_retain_nodes() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        let not_nyc = Function::new_with_args("weight", "return weight !== 'NYC'");
+        let mapping: IndexMapping = g.retain_nodes(&not_nyc).unwrap().into_serde().unwrap();
+        assert_eq!(
+            mapping.nodes,
+            vec![None, Some(1), Some(2), Some(3), Some(0)]
+        );
+        assert_eq!(
+            mapping.edges,
+            vec![
+                None,
+                Some(1),
+                Some(2),
+                None,
+                None,
+                Some(0),
+                Some(3),
+                None,
+                Some(4)
+            ]
+        );
+        assert_eq!(g.node_count(), 4);
+        assert_eq!(g.node_weight(0).unwrap(), "Buenos Aires");
+        assert_eq!(g.edge_count(), 5);
+        assert_eq!(g.edge_endpoints(0), Some(vec![3, 2]));
+        assert_eq!(g.edge_endpoints(3), Some(vec![0, 1]));
+        assert_eq!(g.edge_endpoints(4), Some(vec![0, 3]));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_retain_edges() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        let even = Function::new_with_args("weight, index", "return index % 2 === 0");
+        let mapping: IndexMapping = g.retain_edges(&even).unwrap().into_serde().unwrap();
+        assert_eq!(
+            mapping.nodes,
+            vec![Some(0), Some(1), Some(2), Some(3), Some(4)]
+        );
+        assert_eq!(
+            mapping.edges,
+            vec![
+                Some(0),
+                None,
+                Some(2),
+                None,
+                Some(4),
+                None,
+                Some(3),
+                None,
+                Some(1)
+            ]
+        );
+        assert_eq!(g.edge_endpoints(1), Some(vec![4, 3]));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_report_node_removal_moves() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        let removal = g.remove_node_with_remap(0).unwrap();
+        let weight = Reflect::get(&removal, &JsValue::from_str("weight")).unwrap();
+        assert_eq!(weight, "NYC");
+        let moves: IndexMoves = removal.into_serde().unwrap();
+        assert_eq!(moves.removed_nodes, vec![0]);
+        assert_eq!(moves.removed_edges, vec![4, 3, 7, 0]);
+        assert_eq!(moves.moved_nodes, vec![(4, 0)]);
+        assert_eq!(moves.moved_edges, vec![(5, 0), (6, 3), (8, 4)]);
+        assert_eq!(g.node_weight(0).unwrap(), "Buenos Aires");
+        assert_eq!(g.edge_endpoints(3), Some(vec![0, 1]));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_report_edge_removal_moves() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        let removal = g.remove_edge_with_remap(2).unwrap();
+        let moves: IndexMoves = removal.into_serde().unwrap();
+        assert_eq!(moves.removed_nodes, Vec::<usize>::new());
+        assert_eq!(moves.removed_edges, vec![2]);
+        assert_eq!(moves.moved_edges, vec![(8, 2)]);
+        assert_eq!(g.edge_endpoints(2), Some(vec![4, 3]));
+        assert!(g.remove_edge_with_remap(8).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_clone_graph() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let mut g2 = g.clone_graph();
+        g2.add_node(JsValue::from_str("Lima"));
+        assert_eq!(g.node_count(), 5);
+        assert_eq!(g2.node_count(), 6);
+        assert_eq!(g2.edge_endpoints_all(), g.edge_endpoints_all());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_extract_induced_subgraph() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let sub = g.induced_subgraph(&[4, 1, 3]).unwrap();
+        assert_eq!(sub.node_map(), vec![1, 3, 4]);
+        assert_eq!(sub.edge_map(), vec![1, 6, 8]);
+        let sub_graph = sub.graph();
+        assert_eq!(sub_graph.node_weight(2).unwrap(), "Buenos Aires");
+        assert_eq!(sub_graph.edge_endpoints(1), Some(vec![2, 0]));
+        assert!(g.induced_subgraph(&[9999]).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_extract_edge_subgraph() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let sub = g.edge_subgraph(&[5, 7]).unwrap();
+        assert_eq!(sub.node_map(), vec![0, 2, 3, 4]);
+        assert_eq!(sub.edge_map(), vec![5, 7]);
+        assert_eq!(sub.graph().edge_endpoints(0), Some(vec![2, 1]));
+        assert_eq!(sub.graph().edge_endpoints(1), Some(vec![3, 0]));
+        assert!(g.edge_subgraph(&[9999]).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reverse_graph() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        g.reverse();
+        assert_eq!(g.edge_endpoints(0), Some(vec![0, 1]));
+        assert_eq!(g.externals(Direction::Incoming).unwrap(), vec![2]);
+        assert_eq!(
+            g.neighbors_directed(3, Direction::Outgoing).unwrap(),
+            vec![4, 0, 1]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_get_externals() {
+        let (g, _nodes, _edges) = new_test_graph();
+        assert_eq!(g.externals(Direction::Incoming).unwrap(), vec![4]);
+        assert_eq!(g.externals(Direction::Outgoing).unwrap(), vec![2]);
+        assert_eq!(g.externals(Direction::Both).unwrap(), Vec::<usize>::new());
+        let mut g2 = g.clone_graph();
+        let lima = g2.add_node(JsValue::from_str("Lima"));
+        assert_eq!(g2.externals(Direction::Both).unwrap(), vec![lima]);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_query_by_direction() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        assert_eq!(
+            g.neighbors_directed(3, Direction::Incoming).unwrap(),
+            vec![4, 0, 1]
+        );
+        assert_eq!(
+            g.neighbors_directed(3, Direction::Outgoing).unwrap(),
+            vec![2]
+        );
+        assert_eq!(
+            g.neighbors_directed(3, Direction::Both).unwrap(),
+            g.neighbors_undirected(3).unwrap()
+        );
+        assert_eq!(
+            g.edges_directed(3, Direction::Incoming).unwrap(),
+            vec![8, 3, 1]
+        );
+        assert_eq!(
+            g.edges_directed(3, Direction::Both).unwrap(),
+            vec![5, 8, 3, 1]
+        );
+        assert_eq!(g.degree(3, Direction::Incoming).unwrap(), 3);
+        assert_eq!(g.degree(3, Direction::Both).unwrap(), 4);
+
+        let self_loop = g.add_edge(3, 3, JsValue::NULL).unwrap();
+        assert_eq!(
+            g.neighbors_directed(3, Direction::Both).unwrap(),
+            g.neighbors_undirected(3).unwrap()
+        );
+        assert_eq!(
+            g.neighbors_directed(3, Direction::Both).unwrap(),
+            vec![3, 2, 4, 0, 1]
+        );
+        assert_eq!(
+            g.edges_directed(3, Direction::Both).unwrap(),
+            vec![self_loop, 5, 8, 3, 1]
+        );
+        assert_eq!(g.degree(3, Direction::Both).unwrap(), 6);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_invalid_direction() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let err = g.degree(3, Direction::__Nonexhaustive).unwrap_err();
+        let graph_err: GraphError = err.into_serde().unwrap();
+        assert_eq!(
+            graph_err,
+            GraphError::from_message(
+                "Invalid direction, expected \"incoming\", \"outgoing\" or \"both\""
+            )
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn can_get_degrees() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let degrees = g.degrees();
+        assert_eq!(degrees.incoming(), vec![2, 1, 3, 3, 0]);
+        assert_eq!(degrees.outgoing(), vec![2, 3, 0, 1, 3]);
+    }
 }
@@ -0,0 +1,225 @@
+//! Structural rules for a `DiGraph` which are given at construction time and
+//! enforced whenever an edge is added.
+
+use super::DiGraph;
+use crate::js_helpers::options::get_bool_option;
+use crate::{GraphError, GraphItemType};
+use petgraph::graph::NodeIndex;
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+/// Rules for which edges may be added to a graph. The default allows any
+/// edge, matching petgraph's `Graph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphPolicy {
+    pub allow_parallel_edges: bool,
+    pub allow_self_loops: bool,
+    pub acyclic: bool,
+}
+
+impl Default for GraphPolicy {
+    fn default() -> Self {
+        GraphPolicy {
+            allow_parallel_edges: true,
+            allow_self_loops: true,
+            acyclic: false,
+        }
+    }
+}
+
+impl GraphPolicy {
+    /// Read a policy from a JS options object such as
+    /// `{ allowParallelEdges: false, allowSelfLoops: false, acyclic: true }`.
+    /// Options which aren't given keep their default.
+    pub fn from_options(options: &JsValue) -> Result<Self, JsValue> {
+        let default = GraphPolicy::default();
+        Ok(GraphPolicy {
+            allow_parallel_edges: get_bool_option(
+                options,
+                "allowParallelEdges",
+                default.allow_parallel_edges,
+            )?,
+            allow_self_loops: get_bool_option(options, "allowSelfLoops", default.allow_self_loops)?,
+            acyclic: get_bool_option(options, "acyclic", default.acyclic)?,
+        })
+    }
+}
+
+impl DiGraph {
+    /// Check that a new edge from `a` to `b` can be added: both endpoints
+    /// must exist and the edge must not violate the graph's policy.
+    ///
+    /// For acyclic graphs this searches for a path from `b` to `a`, which
+    /// takes **O(|V| + |E|)** time.
+    pub fn check_new_edge(&self, a: NodeIndex, b: NodeIndex) -> Result<(), GraphError> {
+        let endpoints = [a.index() as u32, b.index() as u32];
+        for &node in &[a, b] {
+            if node.index() >= self.graph.node_count() {
+                return Err(GraphError::new(
+                    "No node exists for given index",
+                    GraphItemType::Node,
+                    node.index() as u32,
+                )
+                .with_endpoints(endpoints));
+            }
+        }
+        if a == b && !self.policy.allow_self_loops {
+            return Err(GraphError::new(
+                "Self loops are not allowed",
+                GraphItemType::Node,
+                endpoints[0],
+            )
+            .with_endpoints(endpoints));
+        }
+        if !self.policy.allow_parallel_edges {
+            if let Some(existing) = self.graph.find_edge(a, b) {
+                return Err(GraphError::new(
+                    "Parallel edges are not allowed",
+                    GraphItemType::Edge,
+                    existing.index() as u32,
+                )
+                .with_endpoints(endpoints));
+            }
+        }
+        if self.policy.acyclic {
+            if let Some(path) = self.find_path(b, a) {
+                return Err(GraphError::from_message("Edge would create a cycle")
+                    .with_endpoints(endpoints)
+                    .with_path(path.into_iter().map(|n| n.index() as u32).collect()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Find a shortest path of nodes from `start` to `goal` following the
+    /// direction of the edges, including both ends.
+    fn find_path(&self, start: NodeIndex, goal: NodeIndex) -> Option<Vec<NodeIndex>> {
+        let mut previous: Vec<Option<NodeIndex>> = vec![None; self.graph.node_count()];
+        let mut queue = VecDeque::new();
+        previous[start.index()] = Some(start);
+        queue.push_back(start);
+        while let Some(node) = queue.pop_front() {
+            if node == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while current != start {
+                    current = previous[current.index()]?;
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.graph.neighbors(node) {
+                if previous[neighbor.index()].is_none() {
+                    previous[neighbor.index()] = Some(node);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use js_sys::{Object, Reflect};
+    use wasm_bindgen_test::*;
+
+    fn new_policy_graph(key: &str) -> DiGraph {
+        let options = Object::new();
+        Reflect::set(
+            &options,
+            &JsValue::from_str(key),
+            &JsValue::from_bool(key == "acyclic"),
+        )
+        .unwrap();
+        let mut g = DiGraph::new(None, None, options.into()).unwrap();
+        let (template, _nodes, _edges) = new_test_graph();
+        for weight in template.node_weights() {
+            g.add_node(weight);
+        }
+        for edge in template.edge_indices() {
+            let endpoints = template.edge_endpoints(edge as usize).unwrap();
+            g.add_edge(endpoints[0], endpoints[1], JsValue::NULL)
+                .unwrap();
+        }
+        g
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_self_loops() {
+        let mut g = new_policy_graph("allowSelfLoops");
+        let err: GraphError = g
+            .add_edge(2, 2, JsValue::NULL)
+            .unwrap_err()
+            .into_serde()
+            .unwrap();
+        assert_eq!(
+            err,
+            GraphError::new("Self loops are not allowed", GraphItemType::Node, 2)
+                .with_endpoints([2, 2])
+        );
+        assert_eq!(g.edge_count(), 9);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_parallel_edges() {
+        let mut g = new_policy_graph("allowParallelEdges");
+        let err: GraphError = g
+            .add_edge(4, 3, JsValue::NULL)
+            .unwrap_err()
+            .into_serde()
+            .unwrap();
+        assert_eq!(
+            err,
+            GraphError::new("Parallel edges are not allowed", GraphItemType::Edge, 8)
+                .with_endpoints([4, 3])
+        );
+        assert_eq!(g.update_edge(4, 3, JsValue::TRUE).unwrap(), 8);
+        assert_eq!(g.add_edge(3, 4, JsValue::NULL).unwrap(), 9);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_cycles() {
+        let mut g = new_policy_graph("acyclic");
+        let err: GraphError = g
+            .add_edge(2, 4, JsValue::NULL)
+            .unwrap_err()
+            .into_serde()
+            .unwrap();
+        assert_eq!(
+            err,
+            GraphError::from_message("Edge would create a cycle")
+                .with_endpoints([2, 4])
+                .with_path(vec![4, 3, 2])
+        );
+        assert!(g.update_edge(2, 2, JsValue::NULL).is_err());
+        assert!(g.add_edge(0, 1, JsValue::NULL).is_err());
+        assert_eq!(g.add_edge(4, 2, JsValue::NULL).unwrap(), 9);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_missing_endpoints() {
+        let mut g = new_policy_graph("acyclic");
+        let err: GraphError = g
+            .add_edge(0, 999, JsValue::NULL)
+            .unwrap_err()
+            .into_serde()
+            .unwrap();
+        assert_eq!(
+            err,
+            GraphError::new("No node exists for given index", GraphItemType::Node, 999)
+                .with_endpoints([0, 999])
+        );
+        assert!(g.update_edge(999, 0, JsValue::NULL).is_err());
+        assert_eq!(g.edge_count(), 9);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_keep_policy_on_copies() {
+        let mut g = new_policy_graph("acyclic").clone_graph();
+        assert!(g.add_edge(2, 4, JsValue::NULL).is_err());
+    }
+}
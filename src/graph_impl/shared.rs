@@ -0,0 +1,117 @@
+//! Methods which `DiGraph` and `UnGraph` share, as neither petgraph nor
+//! `wasm_bindgen` let a generic impl be exported to Javascript. The types only
+//! need a `graph` field holding the petgraph graph.
+
+/// Implement the shared methods for a graph type. `wasm_bindgen` has to be in
+/// scope where it's used.
+macro_rules! impl_graph_methods {
+    ($graph:ident) => {
+        #[wasm_bindgen]
+        impl $graph {
+            #[wasm_bindgen(js_name = nodeCount)]
+            pub fn node_count(&self) -> usize {
+                self.graph.node_count()
+            }
+
+            #[wasm_bindgen(js_name = edgeCount)]
+            pub fn edge_count(&self) -> usize {
+                self.graph.edge_count()
+            }
+
+            #[wasm_bindgen(js_name = isDirected)]
+            pub fn is_directed(&self) -> bool {
+                self.graph.is_directed()
+            }
+
+            #[wasm_bindgen(js_name = addNode)]
+            pub fn add_node(&mut self, weight: JsValue) -> usize {
+                self.graph.add_node(weight).index()
+            }
+
+            #[wasm_bindgen(js_name = nodeWeight)]
+            pub fn node_weight(&self, index: usize) -> Result<JsValue, JsValue> {
+                match self
+                    .graph
+                    .node_weight(petgraph::graph::NodeIndex::new(index))
+                {
+                    Some(weight) => Ok(weight.clone()),
+                    None => Err(JsValue::from_str("No node exists for given index")),
+                }
+            }
+
+            #[wasm_bindgen(js_name = edgeWeight)]
+            pub fn edge_weight(&self, index: usize) -> Result<JsValue, JsValue> {
+                match self
+                    .graph
+                    .edge_weight(petgraph::graph::EdgeIndex::new(index))
+                {
+                    Some(weight) => Ok(weight.clone()),
+                    None => Err(JsValue::from_str("No edge exists for given index")),
+                }
+            }
+
+            #[wasm_bindgen(js_name = edgeEndpoints)]
+            pub fn edge_endpoints(&self, index: usize) -> Option<Vec<usize>> {
+                self.graph
+                    .edge_endpoints(petgraph::graph::EdgeIndex::new(index))
+                    .map(|(i1, i2)| vec![i1.index(), i2.index()])
+            }
+
+            #[wasm_bindgen(js_name = nodeWeights)]
+            pub fn node_weights(&self) -> Vec<JsValue> {
+                self.graph
+                    .raw_nodes()
+                    .iter()
+                    .map(|node| node.weight.clone())
+                    .collect()
+            }
+
+            #[wasm_bindgen(js_name = edgeWeights)]
+            pub fn edge_weights(&self) -> Vec<JsValue> {
+                self.graph
+                    .raw_edges()
+                    .iter()
+                    .map(|edge| edge.weight.clone())
+                    .collect()
+            }
+
+            /// Get the endpoints of all edges in a single pass, ordered by edge
+            /// index.
+            #[wasm_bindgen(js_name = edgeEndpointsAll)]
+            pub fn edge_endpoints_all(&self) -> $crate::graph_impl::EdgeEndpoints {
+                $crate::graph_impl::EdgeEndpoints::from_edges(self.graph.raw_edges())
+            }
+
+            #[wasm_bindgen(js_name = removeNode)]
+            pub fn remove_node(&mut self, index: usize) -> Result<JsValue, JsValue> {
+                match self
+                    .graph
+                    .remove_node(petgraph::graph::NodeIndex::new(index))
+                {
+                    Some(node) => Ok(node),
+                    None => Err(JsValue::from_str("No node exists for given index")),
+                }
+            }
+
+            #[wasm_bindgen(js_name = removeEdge)]
+            pub fn remove_edge(&mut self, index: usize) -> Result<JsValue, JsValue> {
+                match self
+                    .graph
+                    .remove_edge(petgraph::graph::EdgeIndex::new(index))
+                {
+                    Some(edge) => Ok(edge),
+                    None => Err(JsValue::from_str("No edge exists for given index")),
+                }
+            }
+
+            pub fn neighbors(&mut self, index: usize) -> Option<Vec<usize>> {
+                let neighbor_vec: Vec<usize> = self
+                    .graph
+                    .neighbors(petgraph::graph::NodeIndex::new(index))
+                    .map(|neighbor: petgraph::graph::NodeIndex| neighbor.index())
+                    .collect();
+                Some(neighbor_vec)
+            }
+        }
+    };
+}
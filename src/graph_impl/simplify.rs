@@ -0,0 +1,127 @@
+//! Removal of self loops and parallel edges from a `DiGraph`.
+
+use super::{DiGraph, IndexMoves};
+use crate::js_helpers::index_remap::IndexRemap;
+use crate::js_helpers::options::{get_bool_option, get_function_option};
+use petgraph::graph;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl DiGraph {
+    /// Remove self loops and collapse parallel edges into the edge with the
+    /// lowest index. Options:
+    ///
+    /// * `selfLoops` - remove self loops, defaults to `true`.
+    /// * `parallelEdges` - collapse parallel edges, defaults to `true`.
+    /// * `mergeWeights` - function called as `mergeWeights(kept, removed)` for
+    ///   every collapsed edge, its result becomes the weight of the kept edge.
+    ///   Without it the weight of the kept edge is unchanged.
+    ///
+    /// Returns the removed and moved indices in the same format as
+    /// `removeNodeWithRemap`, without the `weight`.
+    pub fn simplify(&mut self, options: JsValue) -> Result<JsValue, JsValue> {
+        let self_loops = get_bool_option(&options, "selfLoops", true)?;
+        let parallel_edges = get_bool_option(&options, "parallelEdges", true)?;
+        let merge_weights = get_function_option(&options, "mergeWeights")?;
+
+        // Work out all changes first, so an exception from `mergeWeights`
+        // leaves the graph untouched.
+        let mut remove = vec![false; self.graph.edge_count()];
+        let mut merged: HashMap<usize, JsValue> = HashMap::new();
+        let mut kept_edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for (index, edge) in self.graph.raw_edges().iter().enumerate() {
+            let (source, target) = (edge.source().index(), edge.target().index());
+            if self_loops && source == target {
+                remove[index] = true;
+            } else if parallel_edges {
+                match kept_edges.get(&(source, target)) {
+                    None => {
+                        kept_edges.insert((source, target), index);
+                    }
+                    Some(&kept) => {
+                        remove[index] = true;
+                        if let Some(merge) = &merge_weights {
+                            let kept_weight = merged
+                                .get(&kept)
+                                .unwrap_or(&self.graph.raw_edges()[kept].weight);
+                            let weight = merge.call2(&JsValue::NULL, kept_weight, &edge.weight)?;
+                            merged.insert(kept, weight);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (kept, weight) in merged {
+            self.graph[graph::EdgeIndex::new(kept)] = weight;
+        }
+        let node_remap = IndexRemap::new(self.graph.node_count());
+        let mut edge_remap = IndexRemap::new(self.graph.edge_count());
+        for edge in self.graph.edge_indices().rev() {
+            if remove[edge.index()] {
+                edge_remap.swap_remove(edge.index());
+                self.graph.remove_edge(edge);
+            }
+        }
+        IndexMoves::new(&node_remap, &edge_remap).create_js_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_helpers::test::new_test_graph;
+    use js_sys::{Function, Object, Reflect};
+    use wasm_bindgen_test::*;
+
+    fn new_options(entries: &[(&str, JsValue)]) -> JsValue {
+        let options = Object::new();
+        for (key, value) in entries {
+            Reflect::set(&options, &JsValue::from_str(key), value).unwrap();
+        }
+        options.into()
+    }
+
+    #[wasm_bindgen_test]
+    fn can_simplify_graph() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        g.add_edge(2, 2, JsValue::NULL).unwrap();
+        g.add_edge(1, 3, JsValue::NULL).unwrap();
+        let moves: IndexMoves = g
+            .simplify(JsValue::UNDEFINED)
+            .unwrap()
+            .into_serde()
+            .unwrap();
+        assert_eq!(moves.removed_edges, vec![10, 9]);
+        assert_eq!(moves.moved_edges, vec![]);
+        assert_eq!(g.edge_count(), 9);
+    }
+
+    #[wasm_bindgen_test]
+    fn can_merge_parallel_edge_weights() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        g.add_edge(4, 4, JsValue::from(1)).unwrap();
+        g.add_edge(1, 0, JsValue::from(2)).unwrap();
+        g.add_edge(1, 0, JsValue::from(3)).unwrap();
+        g.set_edge_weight(0, JsValue::from(4)).unwrap();
+        let options = new_options(&[
+            ("selfLoops", JsValue::FALSE),
+            (
+                "mergeWeights",
+                Function::new_with_args("a, b", "return a + b").into(),
+            ),
+        ]);
+        let moves: IndexMoves = g.simplify(options).unwrap().into_serde().unwrap();
+        assert_eq!(moves.removed_edges, vec![11, 10]);
+        assert_eq!(g.edge_weight(0).unwrap(), 9);
+        assert_eq!(g.edge_endpoints(9), Some(vec![4, 4]));
+    }
+
+    #[wasm_bindgen_test]
+    fn can_reject_invalid_options() {
+        let (mut g, _nodes, _edges) = new_test_graph();
+        let options = new_options(&[("selfLoops", JsValue::from_str("yes"))]);
+        assert!(g.simplify(options).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Undirected counterpart of `DiGraph`. It is mostly used as the result of
+//! converting a `DiGraph`, so it only provides the core graph operations.
+
+use super::DiGraph;
+use crate::utils;
+use petgraph::graph;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct UnGraph {
+    #[wasm_bindgen(skip)]
+    pub graph: graph::UnGraph<JsValue, JsValue>,
+}
+
+impl_graph_methods!(UnGraph);
+
+#[wasm_bindgen]
+impl UnGraph {
+    #[wasm_bindgen(constructor)]
+    pub fn new(node_capacity: Option<usize>, edge_capacity: Option<usize>) -> Self {
+        utils::set_panic_hook();
+        UnGraph {
+            graph: graph::UnGraph::with_capacity(
+                node_capacity.unwrap_or(0),
+                edge_capacity.unwrap_or(0),
+            ),
+        }
+    }
+
+    /// Create a copy of the graph with identical indices.
+    #[wasm_bindgen(js_name = clone)]
+    pub fn clone_graph(&self) -> UnGraph {
+        self.clone()
+    }
+
+    /// Create a directed copy of the graph with identical indices and weights,
+    /// where every edge points from its first to its second endpoint.
+    #[wasm_bindgen(js_name = toDirected)]
+    pub fn to_directed(&self) -> DiGraph {
+        DiGraph::from(self.graph.clone().into_edge_type())
+    }
+
+    #[wasm_bindgen(js_name = addEdge)]
+    pub fn add_edge(&mut self, a: usize, b: usize, weight: JsValue) -> usize {
+        self.graph
+            .add_edge(graph::NodeIndex::new(a), graph::NodeIndex::new(b), weight)
+            .index()
+    }
+
+    #[wasm_bindgen(js_name = updateEdge)]
+    pub fn update_edge(&mut self, a: usize, b: usize, weight: JsValue) -> usize {
+        self.graph
+            .update_edge(graph::NodeIndex::new(a), graph::NodeIndex::new(b), weight)
+            .index()
+    }
+}
+
+impl From<graph::UnGraph<JsValue, JsValue>> for UnGraph {
+    fn from(graph: graph::UnGraph<JsValue, JsValue>) -> Self {
+        UnGraph { graph }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::js_helpers::test::new_test_graph;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn can_convert_between_directed_and_undirected() {
+        let (g, _nodes, _edges) = new_test_graph();
+        let mut ug = g.to_undirected();
+        assert!(!ug.is_directed());
+        assert_eq!(ug.node_count(), 5);
+        assert_eq!(ug.edge_count(), 9);
+        assert_eq!(ug.node_weight(4).unwrap(), "Buenos Aires");
+        assert_eq!(ug.edge_endpoints_all(), g.edge_endpoints_all());
+        assert_eq!(ug.neighbors(2), Some(vec![3, 0, 1]));
+
+        let dg = ug.to_directed();
+        assert!(dg.is_directed());
+        assert_eq!(dg.edge_endpoints_all(), g.edge_endpoints_all());
+        assert_eq!(dg.node_weights(), g.node_weights());
+    }
+}
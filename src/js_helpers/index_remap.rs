@@ -0,0 +1,130 @@
+//! `IndexRemap` keeps track of how indices move while items are removed from
+//! a petgraph `Graph`. Petgraph removes nodes and edges with `swap_remove`, so
+//! every removal can relocate the last item into the freed index. Javascript
+//! code holding on to indices needs to know about these relocations.
+
+use std::collections::HashMap;
+
+/// Record of `swap_remove` style removals over an indexed collection.
+///
+/// Example usage:
+/// ```rust
+/// use petgraph_wasm::js_helpers::index_remap::IndexRemap;
+/// // Collection of 4 items: [0, 1, 2, 3]
+/// let mut remap = IndexRemap::new(4);
+/// // Removing index 1 moves the item from index 3 into its place
+/// remap.swap_remove(1);
+/// assert_eq!(remap.removed(), &[1]);
+/// assert_eq!(remap.moved(), vec![(3, 1)]);
+/// assert_eq!(remap.mapping(), vec![Some(0), None, Some(2), Some(1)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexRemap {
+    original_len: usize,
+    len: usize,
+    /// Original index of items which are no longer at their original index,
+    /// keyed by their current index.
+    origins: HashMap<usize, usize>,
+    /// Original indices of removed items, in order of removal.
+    removed: Vec<usize>,
+}
+
+impl IndexRemap {
+    /// Create an `IndexRemap` for a collection of `len` items.
+    pub fn new(len: usize) -> Self {
+        Self {
+            original_len: len,
+            len,
+            origins: HashMap::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// Get the original index of the item currently at `index`.
+    pub fn original(&self, index: usize) -> usize {
+        *self.origins.get(&index).unwrap_or(&index)
+    }
+
+    /// Record the removal of the item currently at `index`, with the last item
+    /// taking its place. Must be called before the item is actually removed.
+    pub fn swap_remove(&mut self, index: usize) {
+        assert!(index < self.len, "swap_remove index out of bounds");
+        let last = self.len - 1;
+        self.removed.push(self.original(index));
+        let last_origin = self.original(last);
+        self.origins.remove(&last);
+        if index != last {
+            self.origins.insert(index, last_origin);
+        }
+        self.len -= 1;
+    }
+
+    /// Original indices of all removed items, in order of removal.
+    pub fn removed(&self) -> &[usize] {
+        &self.removed
+    }
+
+    /// Pairs of `(original, current)` indices for every item which is still
+    /// present but was relocated, sorted by original index.
+    pub fn moved(&self) -> Vec<(usize, usize)> {
+        let mut moved: Vec<(usize, usize)> = self
+            .origins
+            .iter()
+            .map(|(&current, &original)| (original, current))
+            .collect();
+        moved.sort_unstable();
+        moved
+    }
+
+    /// Mapping from every original index to its current index, or `None` if
+    /// the item was removed.
+    pub fn mapping(&self) -> Vec<Option<usize>> {
+        let mut mapping = vec![None; self.original_len];
+        for current in 0..self.len {
+            mapping[self.original(current)] = Some(current);
+        }
+        mapping
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_remove_last_item() {
+        let mut remap = IndexRemap::new(3);
+        remap.swap_remove(2);
+        assert_eq!(remap.removed(), &[2]);
+        assert_eq!(remap.moved(), vec![]);
+        assert_eq!(remap.mapping(), vec![Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn can_follow_repeated_moves() {
+        let mut remap = IndexRemap::new(5);
+        // [0, 1, 2, 3, 4] -> [4, 1, 2, 3]
+        remap.swap_remove(0);
+        // [4, 1, 2, 3] -> [4, 3, 2]
+        remap.swap_remove(1);
+        // [4, 3, 2] -> [2, 3]
+        remap.swap_remove(0);
+        assert_eq!(remap.removed(), &[0, 1, 4]);
+        assert_eq!(remap.moved(), vec![(2, 0), (3, 1)]);
+        assert_eq!(remap.mapping(), vec![None, None, Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn matches_vec_swap_remove() {
+        let mut items: Vec<usize> = (0..8).collect();
+        let mut remap = IndexRemap::new(items.len());
+        for &index in &[3, 0, 5, 4, 1] {
+            remap.swap_remove(index);
+            items.swap_remove(index);
+        }
+        for (current, &original) in items.iter().enumerate() {
+            assert_eq!(remap.original(current), original);
+            assert_eq!(remap.mapping()[original], Some(current));
+        }
+    }
+}
@@ -2,5 +2,7 @@
 //! needed in addition to the raw petgraph features so that the library is usable
 //! from Javascript.
 
+pub mod index_remap;
+pub mod options;
 pub mod test;
 pub mod vec_tree;
@@ -0,0 +1,99 @@
+//! Helpers for reading plain Javascript option objects, such as
+//! `{ selfLoops: true, mergeWeights: (a, b) => a + b }`. Missing options,
+//! `undefined` and `null` are all treated as not set.
+
+use crate::GraphError;
+use js_sys::{Array, Function, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Get an option from an options object, which itself may be `undefined` or
+/// `null` when no options were given.
+pub fn get_option(options: &JsValue, key: &str) -> Result<Option<JsValue>, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(None);
+    }
+    if !options.is_object() {
+        return Err(GraphError::from_message("Options must be an object").to_js_value());
+    }
+    let value = Reflect::get(options, &JsValue::from_str(key))?;
+    if value.is_undefined() || value.is_null() {
+        Ok(None)
+    } else {
+        Ok(Some(value))
+    }
+}
+
+/// Get a boolean option, or `default` if it isn't set.
+pub fn get_bool_option(options: &JsValue, key: &str, default: bool) -> Result<bool, JsValue> {
+    match get_option(options, key)? {
+        None => Ok(default),
+        Some(value) => value.as_bool().ok_or_else(|| {
+            GraphError::from_message(&format!("Option `{}` must be a boolean", key)).to_js_value()
+        }),
+    }
+}
+
+/// Get a function option, or `None` if it isn't set.
+pub fn get_function_option(options: &JsValue, key: &str) -> Result<Option<Function>, JsValue> {
+    match get_option(options, key)? {
+        None => Ok(None),
+        Some(value) => value.dyn_into::<Function>().map(Some).map_err(|_| {
+            GraphError::from_message(&format!("Option `{}` must be a function", key)).to_js_value()
+        }),
+    }
+}
+
+/// Get a string option, or `None` if it isn't set.
+pub fn get_string_option(options: &JsValue, key: &str) -> Result<Option<String>, JsValue> {
+    match get_option(options, key)? {
+        None => Ok(None),
+        Some(value) => value.as_string().map(Some).ok_or_else(|| {
+            GraphError::from_message(&format!("Option `{}` must be a string", key)).to_js_value()
+        }),
+    }
+}
+
+/// Get an array of strings option, or `None` if it isn't set.
+pub fn get_string_array_option(
+    options: &JsValue,
+    key: &str,
+) -> Result<Option<Vec<String>>, JsValue> {
+    let error = || {
+        GraphError::from_message(&format!("Option `{}` must be an array of strings", key))
+            .to_js_value()
+    };
+    match get_option(options, key)? {
+        None => Ok(None),
+        Some(value) if Array::is_array(&value) => Array::from(&value)
+            .iter()
+            .map(|item| item.as_string().ok_or_else(error))
+            .collect::<Result<_, _>>()
+            .map(Some),
+        Some(_) => Err(error()),
+    }
+}
+
+/// Get an array of node or edge indices option, or `None` if it isn't set.
+/// Every entry must be a non-negative integer which fits in a `u32`.
+pub fn get_index_array_option(options: &JsValue, key: &str) -> Result<Option<Vec<usize>>, JsValue> {
+    let error = || {
+        GraphError::from_message(&format!("Option `{}` must be an array of indices", key))
+            .to_js_value()
+    };
+    let index = |item: JsValue| match item.as_f64() {
+        Some(index) if index >= 0.0 && index <= u32::MAX as f64 && index.fract() == 0.0 => {
+            Ok(index as usize)
+        }
+        _ => Err(error()),
+    };
+    match get_option(options, key)? {
+        None => Ok(None),
+        Some(value) if Array::is_array(&value) => Array::from(&value)
+            .iter()
+            .map(index)
+            .collect::<Result<_, _>>()
+            .map(Some),
+        Some(_) => Err(error()),
+    }
+}
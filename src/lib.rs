@@ -1,4 +1,5 @@
 pub mod algo;
+pub mod formats;
 pub mod graph_impl;
 pub mod js_helpers;
 mod utils;
@@ -25,23 +26,78 @@ pub struct GraphItem {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GraphError {
     message: String,
-    component: GraphItem,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    component: Option<GraphItem>,
+    /// Source and target of a rejected edge, which has no index of its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    endpoints: Option<[u32; 2]>,
+    /// Path of node indices related to the error, such as an existing path
+    /// which a rejected edge would have turned into a cycle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<Vec<u32>>,
+    /// Position in parsed text where the error was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    location: Option<TextLocation>,
+}
+
+/// 1-based line and column in a parsed text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextLocation {
+    pub line: u32,
+    pub column: u32,
 }
 
 impl GraphError {
     pub fn new(message: &str, t: GraphItemType, i: u32) -> Self {
         GraphError {
             message: String::from(message),
-            component: GraphItem {
+            component: Some(GraphItem {
                 component_type: t,
                 id: i,
-            },
+            }),
+            endpoints: None,
+            path: None,
+            location: None,
+        }
+    }
+
+    /// Create an error which isn't about a particular node or edge, such as
+    /// an invalid argument.
+    pub fn from_message(message: &str) -> Self {
+        GraphError {
+            message: String::from(message),
+            component: None,
+            endpoints: None,
+            path: None,
+            location: None,
         }
     }
 
+    pub fn with_endpoints(mut self, endpoints: [u32; 2]) -> Self {
+        self.endpoints = Some(endpoints);
+        self
+    }
+
+    pub fn with_path(mut self, path: Vec<u32>) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn with_location(mut self, line: u32, column: u32) -> Self {
+        self.location = Some(TextLocation { line, column });
+        self
+    }
+
     pub fn create_js_value(&self) -> Result<JsValue, String> {
         JsValue::from_serde(&self).map_err(|_| String::from("Failed to transform graph_error"))
     }
+
+    /// Like `create_js_value`, but falls back to the failure message as a JS
+    /// string so it can directly be used as the error of a `Result`.
+    pub fn to_js_value(&self) -> JsValue {
+        self.create_js_value()
+            .unwrap_or_else(|failure_msg| JsValue::from_str(&failure_msg))
+    }
 }
 
 impl From<Cycle<NodeIndex<u32>>> for GraphError {